@@ -16,5 +16,29 @@ pub enum GraphsError {
     MaxMembersReached,
     EmptyMembers,
     PartialExecution,
-    InvalidExternalAuthority
+    InvalidExternalAuthority,
+    OpenTransactions,
+    InvalidWeight,
+    InvalidPermissions,
+    MissingPermission,
+    TimeLockNotElapsed,
+    TransactionExpired,
+    TransactionNotExpired,
+    TransactionNotStale,
+    SpendingLimitExceeded,
+    InvalidGuardian,
+    InvalidSignatures,
+    InvalidDelegation,
+    InvalidInternalInstruction,
+    ExternalAuthorityRenounced,
+    InvalidMember,
+    InvalidRecoveryKey,
+    InvalidRecovery,
+    RecoveryNotApproved,
+    RecoveryDelayNotElapsed,
+    InvalidMetadata,
+    UnclosedTransactions,
+    OpenSpendingLimits,
+    SelfGovernedMultisig,
+    ThresholdBpsEnabled
 }
\ No newline at end of file
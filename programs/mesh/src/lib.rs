@@ -1,11 +1,20 @@
 use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
 
 use state::mesh::*;
+use state::spending_limit::*;
+use state::delegation::*;
+use state::rotation::*;
+use state::recovery::*;
+use state::metadata::*;
 pub mod state;
 
 use errors::*;
 pub mod errors;
 
+use utils::*;
+pub mod utils;
+
 // INSERT PROGRAM ID
 declare_id!("");
 
@@ -14,16 +23,27 @@ pub mod mesh {
 
     use std::{convert::{TryInto}};
 
-    use anchor_lang::solana_program::{program::{invoke_signed, invoke}, system_instruction::transfer};
+    use anchor_lang::solana_program::{program::invoke_signed, system_instruction::transfer};
 
     use super::*;
     
     // instruction to create a multisig
-    pub fn create(ctx: Context<Create>, external_authority: Pubkey, threshold:u16, create_key: Pubkey, members: Vec<Pubkey>) -> Result<()> {
-        // sort the members and remove duplicates
-        let mut members = members;
-        members.sort();
-        members.dedup();
+    // if no external authority is given, the multisig governs itself: the external
+    // authority is set to the multisig's internal authority (index 0), so config
+    // changes must be proposed, approved and executed as transactions.
+    // the create key has to sign, so only its holder can ever use its address -
+    // including re-creating a multisig that was closed, which revives its vaults
+    pub fn create(ctx: Context<Create>, external_authority: Option<Pubkey>, threshold:u16, members: Vec<Pubkey>, weights: Vec<u16>) -> Result<()> {
+        // each member needs a voting weight of at least 1
+        if members.len() != weights.len() {
+            return err!(GraphsError::InvalidNumberOfAccounts);
+        }
+        if weights.iter().any(|&w| w < 1) {
+            return err!(GraphsError::InvalidWeight);
+        }
+
+        // sort the members and remove duplicates, keeping the weights paired
+        let (members, weights) = sort_members(members, weights);
 
         // check we don't exceed u16
         let total_members = members.len();
@@ -36,97 +56,127 @@ pub mod mesh {
             return err!(GraphsError::MaxMembersReached);
         }
 
-        // make sure threshold is valid
-        if usize::from(threshold) < 1 || usize::from(threshold) > total_members {
+        // make sure threshold is valid, the threshold is measured in voting weight
+        let total_weight: u32 = weights.iter().map(|&w| u32::from(w)).sum();
+        if threshold < 1 || u32::from(threshold) > total_weight {
             return err!(GraphsError::InvalidThreshold);
         }
 
+        let external_authority = match external_authority {
+            Some(external_authority) => external_authority,
+            None => {
+                let (internal_authority, _) = Pubkey::find_program_address(&[
+                    b"squad",
+                    ctx.accounts.multisig.key().as_ref(),
+                    &Ms::INTERNAL_AUTHORITY_INDEX.to_le_bytes(),
+                    b"authority"
+                ], ctx.program_id);
+                internal_authority
+            }
+        };
+
         ctx.accounts.multisig.init(
             external_authority,
             threshold,
-            create_key,
+            ctx.accounts.create_key.key(),
             members,
+            weights,
             *ctx.bumps.get("multisig").unwrap(),
         )
     }
 
     // instruction to add a member/key to the multisig and reallocate space if neccessary
-    pub fn add_member(ctx: Context<MsAuthRealloc>, new_member: Pubkey) -> Result<()> {
+    // for this and the other config changes, retally keeps in-flight transactions and re-tallies
+    // their votes against the new config, instead of deprecating them
+    pub fn add_member(ctx: Context<MsAuthRealloc>, new_member: Pubkey, weight: u16, retally: bool) -> Result<()> {
         // if max is already reached, we can't have more members
         if ctx.accounts.multisig.keys.len() >= usize::from(u16::MAX) {
             return err!(GraphsError::MaxMembersReached);
         }
 
+        if weight < 1 {
+            return err!(GraphsError::InvalidWeight);
+        }
+
+        // guardians can't be members
+        if ctx.accounts.multisig.is_guardian(new_member).is_some() {
+            return err!(GraphsError::InvalidGuardian);
+        }
+
         // check if realloc is needed
         let multisig_account_info = ctx.accounts.multisig.to_account_info();
-        if *multisig_account_info.owner != mesh::ID {
-            return err!(GraphsError::InvalidInstructionAccount);
-        }
         let curr_data_size = multisig_account_info.data.borrow().len();
-        let spots_left = ((curr_data_size - Ms::SIZE_WITHOUT_MEMBERS) / 32 ) - ctx.accounts.multisig.keys.len();
-
-        // if not enough, add (10 * 32) to size - bump it up by 10 accounts
-        if spots_left < 1 {
-            // add space for 10 more keys
-            let needed_len = curr_data_size + ( 10 * 32 );
-            // reallocate more space
-            AccountInfo::realloc(&multisig_account_info, needed_len, false)?;
-            // if more lamports are needed, transfer them to the account
-            let rent_exempt_lamports = ctx.accounts.rent.minimum_balance(needed_len).max(1);
-            let top_up_lamports = rent_exempt_lamports.saturating_sub(ctx.accounts.multisig.to_account_info().lamports());
-            if top_up_lamports > 0 {
-                invoke(
-                    &transfer(ctx.accounts.external_authority.key, &ctx.accounts.multisig.key(), top_up_lamports),
-                    &[
-                        ctx.accounts.external_authority.to_account_info().clone(),
-                        multisig_account_info.clone(),
-                        ctx.accounts.system_program.to_account_info().clone(),
-                    ],
-                )?;
-            }
+
+        // if there is no spot left, bump it up by 10 accounts
+        if curr_data_size < ctx.accounts.multisig.size_with_slack(1) {
+            let needed_len = ctx.accounts.multisig.size_with_slack(Ms::MEMBER_REALLOC_STEP);
+            grow_account(
+                &multisig_account_info,
+                &ctx.accounts.external_authority.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent,
+                needed_len,
+            )?;
         }
         ctx.accounts.multisig.reload()?;
-        ctx.accounts.multisig.add_member(new_member)?;
-        let new_index = ctx.accounts.multisig.transaction_index;
-        ctx.accounts.multisig.set_change_index(new_index)
+        ctx.accounts.multisig.add_member(new_member, weight)?;
+        ctx.accounts.multisig.record_change(retally)
     }
 
     // instruction to remove a member/key from the multisig
-    pub fn remove_member(ctx: Context<MsAuth>, old_member: Pubkey) -> Result<()> {
+    pub fn remove_member(ctx: Context<MsAuth>, old_member: Pubkey, retally: bool) -> Result<()> {
         // if there is only one key in this multisig, reject the removal
         if ctx.accounts.multisig.keys.len() == 1 {
             return err!(GraphsError::CannotRemoveSoloMember);
         }
         ctx.accounts.multisig.remove_member(old_member)?;
 
-        // if the number of keys is now less than the threshold, adjust it
-        if ctx.accounts.multisig.keys.len() < usize::from(ctx.accounts.multisig.threshold) {
-            let new_threshold: u16 = ctx.accounts.multisig.keys.len().try_into().unwrap();
+        // there must be at least one member left that can vote
+        if ctx.accounts.multisig.total_weight() < 1 {
+            return err!(GraphsError::CannotRemoveSoloMember);
+        }
+
+        // if the total weight is now less than the threshold, adjust it
+        if ctx.accounts.multisig.total_weight() < u32::from(ctx.accounts.multisig.threshold) {
+            let new_threshold: u16 = ctx.accounts.multisig.total_weight().try_into().unwrap();
             ctx.accounts.multisig.change_threshold(new_threshold)?;
         }
-        let new_index = ctx.accounts.multisig.transaction_index;
-        ctx.accounts.multisig.set_change_index(new_index)
+        ctx.accounts.multisig.record_change(retally)?;
+
+        // give back the space of removed members, keeping some slots for future additions
+        let compact_len = ctx.accounts.multisig.size_with_slack(usize::from(ctx.accounts.multisig.member_slack));
+        shrink_account(
+            &ctx.accounts.multisig.to_account_info(),
+            &ctx.accounts.external_authority.to_account_info(),
+            compact_len,
+        )
     }
 
     // instruction to remove a member/key from the multisig and change the threshold
     pub fn remove_member_and_change_threshold<'info>(
-        ctx: Context<'_,'_,'_,'info, MsAuth<'info>>, old_member: Pubkey, new_threshold: u16
+        ctx: Context<'_,'_,'_,'info, MsAuth<'info>>, old_member: Pubkey, new_threshold: u16, retally: bool
     ) -> Result<()> {
+        if ctx.accounts.multisig.threshold_bps.is_some() {
+            return err!(GraphsError::ThresholdBpsEnabled);
+        }
         remove_member(
             Context::new(
                 ctx.program_id,
                 ctx.accounts,
                 ctx.remaining_accounts,
                 ctx.bumps.clone()
-            ), old_member
+            ), old_member, retally
         )?;
-        change_threshold(ctx, new_threshold)
+        change_threshold(ctx, new_threshold, retally)
     }
 
     // instruction to add a member/key from the multisig and change the threshold
     pub fn add_member_and_change_threshold<'info>(
-        ctx: Context<'_,'_,'_,'info, MsAuthRealloc<'info>>, new_member: Pubkey, new_threshold: u16
+        ctx: Context<'_,'_,'_,'info, MsAuthRealloc<'info>>, new_member: Pubkey, weight: u16, new_threshold: u16, retally: bool
     ) -> Result<()> {
+        if ctx.accounts.multisig.threshold_bps.is_some() {
+            return err!(GraphsError::ThresholdBpsEnabled);
+        }
         // add the member
         add_member(
             Context::new(
@@ -134,35 +184,345 @@ pub mod mesh {
                 ctx.accounts,
                 ctx.remaining_accounts,
                 ctx.bumps.clone()
-            ), new_member
+            ), new_member, weight, retally
         )?;
 
         // check that the threshold value is valid
-        if ctx.accounts.multisig.keys.len() < usize::from(new_threshold) {
-            let new_threshold: u16 = ctx.accounts.multisig.keys.len().try_into().unwrap();
+        if ctx.accounts.multisig.total_weight() < u32::from(new_threshold) {
+            let new_threshold: u16 = ctx.accounts.multisig.total_weight().try_into().unwrap();
             ctx.accounts.multisig.change_threshold(new_threshold)?;
         } else if new_threshold < 1 {
             return err!(GraphsError::InvalidThreshold);
         } else {
             ctx.accounts.multisig.change_threshold(new_threshold)?;
         }
-        let new_index = ctx.accounts.multisig.transaction_index;
-        ctx.accounts.multisig.set_change_index(new_index)
+        ctx.accounts.multisig.record_change(retally)
+    }
+
+    // instruction to add and remove several members and set a new threshold at once
+    // the changes are validated as a whole, so no intermediate state is ever stored
+    pub fn reconfigure(ctx: Context<MsAuthRealloc>, add_members: Vec<Pubkey>, add_weights: Vec<u16>, remove_members: Vec<Pubkey>, new_threshold: u16, retally: bool) -> Result<()> {
+        if ctx.accounts.multisig.threshold_bps.is_some() {
+            return err!(GraphsError::ThresholdBpsEnabled);
+        }
+        if add_members.len() != add_weights.len() {
+            return err!(GraphsError::InvalidNumberOfAccounts);
+        }
+        if add_weights.iter().any(|&w| w < 1) {
+            return err!(GraphsError::InvalidWeight);
+        }
+        // guardians can't be members
+        if add_members.iter().any(|&m| ctx.accounts.multisig.is_guardian(m).is_some()) {
+            return err!(GraphsError::InvalidGuardian);
+        }
+        // added keys must be new, removed keys must be current members,
+        // and no key can be listed twice across both lists
+        if add_members.iter().any(|&m| ctx.accounts.multisig.is_member(m).is_some()) {
+            return err!(GraphsError::InvalidMember);
+        }
+        if remove_members.iter().any(|&m| ctx.accounts.multisig.is_member(m).is_none()) {
+            return err!(GraphsError::InvalidMember);
+        }
+        let mut listed_keys: Vec<Pubkey> = add_members.iter().chain(remove_members.iter()).copied().collect();
+        listed_keys.sort();
+        listed_keys.dedup();
+        if listed_keys.len() != add_members.len() + remove_members.len() {
+            return err!(GraphsError::InvalidMember);
+        }
+
+        let add_members: Vec<(Pubkey, u16)> = add_members.into_iter().zip(add_weights).collect();
+        ctx.accounts.multisig.reconfigure(add_members, &remove_members, new_threshold)?;
+
+        // validate the resulting member set and threshold
+        let total_weight = ctx.accounts.multisig.total_weight();
+        if ctx.accounts.multisig.keys.is_empty() || total_weight < 1 {
+            return err!(GraphsError::EmptyMembers);
+        }
+        if ctx.accounts.multisig.keys.len() > usize::from(u16::MAX) {
+            return err!(GraphsError::MaxMembersReached);
+        }
+        if new_threshold < 1 || u32::from(new_threshold) > total_weight {
+            return err!(GraphsError::InvalidThreshold);
+        }
+        ctx.accounts.multisig.record_change(retally)?;
+
+        // resize once for the final member set, keeping some slots for future additions
+        let multisig_account_info = ctx.accounts.multisig.to_account_info();
+        if multisig_account_info.data_len() < ctx.accounts.multisig.size_with_slack(0) {
+            grow_account(
+                &multisig_account_info,
+                &ctx.accounts.external_authority.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent,
+                ctx.accounts.multisig.size_with_slack(Ms::MEMBER_REALLOC_STEP),
+            )
+        } else {
+            shrink_account(
+                &multisig_account_info,
+                &ctx.accounts.external_authority.to_account_info(),
+                ctx.accounts.multisig.size_with_slack(usize::from(ctx.accounts.multisig.member_slack)),
+            )
+        }
+    }
+
+    // instruction to swap a member's key, keeping its weight, permissions and the threshold
+    pub fn replace_member(ctx: Context<MsAuth>, old_member: Pubkey, new_member: Pubkey, retally: bool) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        if ms.is_member(old_member).is_none() {
+            return err!(GraphsError::InvalidMember);
+        }
+        // the new key can't already hold a seat or be a guardian
+        if ms.is_member(new_member).is_some() {
+            return err!(GraphsError::InvalidMember);
+        }
+        if ms.is_guardian(new_member).is_some() {
+            return err!(GraphsError::InvalidGuardian);
+        }
+        ms.replace_member(old_member, new_member)?;
+        ms.record_change(retally)
+    }
+
+    // instruction for a member to request moving their seat to a new key
+    // the new key co-signs, and the external authority has to approve the rotation
+    pub fn request_member_rotation(ctx: Context<RequestMemberRotation>) -> Result<()> {
+        ctx.accounts.rotation.init(
+            ctx.accounts.multisig.key(),
+            ctx.accounts.old_member.key(),
+            ctx.accounts.new_member.key(),
+            *ctx.bumps.get("rotation").unwrap(),
+        )
+    }
+
+    // instruction for the external authority to approve a requested rotation
+    pub fn approve_member_rotation(ctx: Context<ApproveMemberRotation>, retally: bool) -> Result<()> {
+        let old_member = ctx.accounts.rotation.old_member;
+        let new_member = ctx.accounts.rotation.new_member;
+        let ms = &mut ctx.accounts.multisig;
+        if ms.is_member(new_member).is_some() {
+            return err!(GraphsError::InvalidMember);
+        }
+        if ms.is_guardian(new_member).is_some() {
+            return err!(GraphsError::InvalidGuardian);
+        }
+        ms.replace_member(old_member, new_member)?;
+        ms.record_change(retally)
+    }
+
+    // instruction for a member to withdraw their rotation request, returning the rent
+    pub fn cancel_member_rotation(_ctx: Context<CancelMemberRotation>) -> Result<()> {
+        Ok(())
     }
 
     // instruction to change the threshold
-    pub fn change_threshold(ctx: Context<MsAuth>, new_threshold: u16) -> Result<()> {
+    // while a basis point threshold is set the absolute threshold isn't used, so changing it
+    // fails until the basis points are cleared. the same goes for the other instructions
+    // that take a new threshold
+    pub fn change_threshold(ctx: Context<MsAuth>, new_threshold: u16, retally: bool) -> Result<()> {
+        if ctx.accounts.multisig.threshold_bps.is_some() {
+            return err!(GraphsError::ThresholdBpsEnabled);
+        }
         // if the new threshold value is valid
-        if ctx.accounts.multisig.keys.len() < usize::from(new_threshold) {
-            let new_threshold: u16 = ctx.accounts.multisig.keys.len().try_into().unwrap();
+        if ctx.accounts.multisig.total_weight() < u32::from(new_threshold) {
+            let new_threshold: u16 = ctx.accounts.multisig.total_weight().try_into().unwrap();
             ctx.accounts.multisig.change_threshold(new_threshold)?;
         } else if new_threshold < 1 {
             return err!(GraphsError::InvalidThreshold);
         } else {
             ctx.accounts.multisig.change_threshold(new_threshold)?;
         }
-        let new_index = ctx.accounts.multisig.transaction_index;
-        ctx.accounts.multisig.set_change_index(new_index)
+        ctx.accounts.multisig.record_change(retally)
+    }
+
+    // instruction to set the threshold as basis points of the total voting weight (rounded up),
+    // so it stays proportional as members are added or removed. passing None goes back
+    // to the absolute threshold
+    pub fn set_threshold_bps(ctx: Context<MsAuth>, threshold_bps: Option<u16>, retally: bool) -> Result<()> {
+        if let Some(bps) = threshold_bps {
+            if !(1..=Ms::MAX_THRESHOLD_BPS).contains(&bps) {
+                return err!(GraphsError::InvalidThreshold);
+            }
+        }
+        ctx.accounts.multisig.threshold_bps = threshold_bps;
+        ctx.accounts.multisig.record_change(retally)
+    }
+
+    // instruction to set explicit reject and cancel thresholds. passing None restores the
+    // default: rejected once the approval threshold can no longer be reached, and cancelled
+    // with the same weight as the approval threshold
+    pub fn change_reject_cancel_thresholds(ctx: Context<MsAuth>, reject_threshold: Option<u16>, cancel_threshold: Option<u16>, retally: bool) -> Result<()> {
+        let total_weight = ctx.accounts.multisig.total_weight();
+        for threshold in [reject_threshold, cancel_threshold].iter().flatten() {
+            if *threshold < 1 || u32::from(*threshold) > total_weight {
+                return err!(GraphsError::InvalidThreshold);
+            }
+        }
+        ctx.accounts.multisig.reject_threshold = reject_threshold;
+        ctx.accounts.multisig.cancel_threshold = cancel_threshold;
+        ctx.accounts.multisig.record_change(retally)
+    }
+
+    // instruction to set a threshold for transactions using a specific authority (vault),
+    // overriding the multisig threshold. passing no threshold removes the override
+    pub fn set_authority_threshold(ctx: Context<MsAuthRealloc>, authority_index: u32, threshold: Option<u16>, retally: bool) -> Result<()> {
+        if let Some(threshold) = threshold {
+            if threshold < 1 || u32::from(threshold) > ctx.accounts.multisig.total_weight() {
+                return err!(GraphsError::InvalidThreshold);
+            }
+        }
+        ctx.accounts.multisig.set_authority_threshold(authority_index, threshold)?;
+
+        // make room for a new entry if needed
+        let needed_len = ctx.accounts.multisig.size_with_slack(0);
+        grow_account(
+            &ctx.accounts.multisig.to_account_info(),
+            &ctx.accounts.external_authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            needed_len,
+        )?;
+        ctx.accounts.multisig.record_change(retally)
+    }
+
+    // instruction to add a guardian, a non-member key that can veto any pending transaction
+    pub fn add_guardian(ctx: Context<MsAuthRealloc>, guardian: Pubkey) -> Result<()> {
+        // guardians can't propose, approve or execute, so they can't be members
+        if ctx.accounts.multisig.is_member(guardian).is_some() {
+            return err!(GraphsError::InvalidGuardian);
+        }
+        ctx.accounts.multisig.add_guardian(guardian)?;
+
+        let needed_len = ctx.accounts.multisig.size_with_slack(0);
+        grow_account(
+            &ctx.accounts.multisig.to_account_info(),
+            &ctx.accounts.external_authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            needed_len,
+        )
+    }
+
+    // instruction to set the keys that can recover the multisig, the approvals needed and the delay
+    // before a recovery can be finalized. a threshold of 0 with no keys disables recovery,
+    // pending recoveries can then no longer be approved or finalized
+    pub fn set_recovery(ctx: Context<MsAuthRealloc>, recovery_keys: Vec<Pubkey>, recovery_threshold: u16, recovery_delay_seconds: u32) -> Result<()> {
+        let mut recovery_keys = recovery_keys;
+        recovery_keys.sort();
+        recovery_keys.dedup();
+        if usize::from(recovery_threshold) > recovery_keys.len() || (recovery_threshold < 1 && !recovery_keys.is_empty()) {
+            return err!(GraphsError::InvalidThreshold);
+        }
+        ctx.accounts.multisig.set_recovery(recovery_keys, recovery_threshold, recovery_delay_seconds)?;
+
+        let needed_len = ctx.accounts.multisig.size_with_slack(0);
+        grow_account(
+            &ctx.accounts.multisig.to_account_info(),
+            &ctx.accounts.external_authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            needed_len,
+        )
+    }
+
+    // instruction for a recovery key to propose a new external authority and/or member set
+    // an empty member list keeps the current members
+    pub fn initiate_recovery(ctx: Context<InitiateRecovery>, new_external_authority: Option<Pubkey>, new_members: Vec<Pubkey>, new_weights: Vec<u16>, new_threshold: u16) -> Result<()> {
+        if new_external_authority.is_none() && new_members.is_empty() {
+            return err!(GraphsError::InvalidRecovery);
+        }
+        if new_members.len() != new_weights.len() {
+            return err!(GraphsError::InvalidNumberOfAccounts);
+        }
+        if new_weights.iter().any(|&w| w < 1) {
+            return err!(GraphsError::InvalidWeight);
+        }
+        let (new_members, new_weights) = sort_members(new_members, new_weights);
+        if !new_members.is_empty() {
+            if new_members.len() > usize::from(u16::MAX) {
+                return err!(GraphsError::MaxMembersReached);
+            }
+            let total_weight: u32 = new_weights.iter().map(|&w| u32::from(w)).sum();
+            if new_threshold < 1 || u32::from(new_threshold) > total_weight {
+                return err!(GraphsError::InvalidThreshold);
+            }
+            if new_members.iter().any(|&m| ctx.accounts.multisig.is_guardian(m).is_some()) {
+                return err!(GraphsError::InvalidGuardian);
+            }
+        }
+
+        let recovery = &mut ctx.accounts.recovery;
+        recovery.init(
+            ctx.accounts.multisig.key(),
+            ctx.accounts.recovery_key.key(),
+            new_external_authority,
+            *ctx.bumps.get("recovery").unwrap(),
+        )?;
+        recovery.set_new_members(new_members, new_weights, new_threshold)?;
+        recovery.approve(ctx.accounts.recovery_key.key())?;
+        if recovery.tally(&ctx.accounts.multisig) >= usize::from(ctx.accounts.multisig.recovery_threshold) {
+            recovery.approved_at = Some(Clock::get()?.unix_timestamp);
+        }
+        Ok(())
+    }
+
+    // instruction for a recovery key to approve a pending recovery
+    // the delay starts once the recovery threshold is reached
+    pub fn approve_recovery(ctx: Context<ApproveRecovery>) -> Result<()> {
+        let recovery = &mut ctx.accounts.recovery;
+        recovery.approve(ctx.accounts.recovery_key.key())?;
+        if recovery.approved_at.is_none() && recovery.tally(&ctx.accounts.multisig) >= usize::from(ctx.accounts.multisig.recovery_threshold) {
+            recovery.approved_at = Some(Clock::get()?.unix_timestamp);
+        }
+
+        // recovery keys added after the recovery was initiated need extra space
+        let needed_len = ctx.accounts.recovery.size();
+        grow_account(
+            &ctx.accounts.recovery.to_account_info(),
+            &ctx.accounts.recovery_key.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &Rent::get()?,
+            needed_len,
+        )
+    }
+
+    // instruction for a member or the external authority to cancel a pending recovery
+    pub fn cancel_recovery(_ctx: Context<CancelRecovery>) -> Result<()> {
+        Ok(())
+    }
+
+    // instruction to apply an approved recovery once the delay has passed
+    pub fn finalize_recovery(ctx: Context<FinalizeRecovery>) -> Result<()> {
+        let recovery = &ctx.accounts.recovery;
+        let ms = &mut ctx.accounts.multisig;
+        if recovery.tally(ms) < usize::from(ms.recovery_threshold) {
+            return err!(GraphsError::RecoveryNotApproved);
+        }
+        if !recovery.delay_elapsed(ms.recovery_delay_seconds) {
+            return err!(GraphsError::RecoveryDelayNotElapsed);
+        }
+
+        if let Some(new_external_authority) = recovery.new_external_authority {
+            ms.external_authority = new_external_authority;
+            ms.pending_external_authority = None;
+        }
+        if !recovery.new_members.is_empty() {
+            ms.reset_members(recovery.new_members.clone(), recovery.new_weights.clone(), recovery.new_threshold)?;
+        }
+        // a recovery always deprecates in-flight transactions
+        ms.record_change(false)?;
+
+        let needed_len = ms.size_with_slack(Ms::MEMBER_REALLOC_STEP);
+        grow_account(
+            &ms.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            needed_len,
+        )
+    }
+
+    // instruction to remove a guardian
+    pub fn remove_guardian(ctx: Context<MsAuth>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.multisig.remove_guardian(guardian)
     }
 
     // instruction to increase the authority value tracked in the multisig
@@ -172,6 +532,54 @@ pub mod mesh {
         ctx.accounts.multisig.add_authority()
     }
 
+    // instruction to set the number of spare member slots kept when member removals
+    // shrink the multisig account
+    pub fn set_member_slack(ctx: Context<MsAuth>, slack: u16) -> Result<()> {
+        ctx.accounts.multisig.member_slack = slack;
+        Ok(())
+    }
+
+    // instruction to shrink the multisig account down to its current members plus
+    // the specified number of spare member slots, refunding the surplus rent
+    pub fn compact_multisig(ctx: Context<MsAuth>, slack: u16) -> Result<()> {
+        let compact_len = ctx.accounts.multisig.size_with_slack(usize::from(slack));
+        shrink_account(
+            &ctx.accounts.multisig.to_account_info(),
+            &ctx.accounts.external_authority.to_account_info(),
+            compact_len,
+        )
+    }
+
+    // instruction to grant permissions (initiate, vote, execute) to a member
+    pub fn grant_permissions(ctx: Context<MsAuth>, member: Pubkey, permissions: u8, retally: bool) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        let ind = ms.is_member(member).ok_or(GraphsError::KeyNotInMultisig)?;
+        if permissions & !Ms::PERMISSION_ALL != 0 {
+            return err!(GraphsError::InvalidPermissions);
+        }
+        let new_permissions = ms.permissions[ind] | permissions;
+        ms.set_permissions(member, new_permissions)?;
+        ms.record_change(retally)
+    }
+
+    // instruction to revoke permissions (initiate, vote, execute) from a member
+    // if revoking the vote lowers the total voting weight below the threshold, the threshold is adjusted
+    pub fn revoke_permissions(ctx: Context<MsAuth>, member: Pubkey, permissions: u8, retally: bool) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        let ind = ms.is_member(member).ok_or(GraphsError::KeyNotInMultisig)?;
+        if permissions & !Ms::PERMISSION_ALL != 0 {
+            return err!(GraphsError::InvalidPermissions);
+        }
+        let new_permissions = ms.permissions[ind] & !permissions;
+        ms.set_permissions(member, new_permissions)?;
+
+        // there must be at least one member left that can vote
+        if ms.total_weight() < 1 {
+            return err!(GraphsError::InvalidPermissions);
+        }
+        ms.record_change(retally)
+    }
+
     // instruction to change the external execute setting, which allows
     // non-members or programs to execute a transaction.
     pub fn set_external_execute(ctx: Context<MsAuth>, setting: bool) -> Result<()> {
@@ -180,12 +588,23 @@ pub mod mesh {
         Ok(())
     }
 
+    // instruction to change the time lock, the number of seconds that must pass
+    // between a transaction becoming execute ready and being executed. This gives
+    // members a window to cancel. The current setting applies to all pending transactions.
+    pub fn set_time_lock(ctx: Context<MsAuth>, time_lock_seconds: u32) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        ms.time_lock_seconds = time_lock_seconds;
+        Ok(())
+    }
+
     // instruction to create a transaction
     // each transaction is tied to a single authority, and must be specified when
     // creating the instruction below. authority 0 is reserved for internal
-    // instructions, whereas authorities 1 or greater refer to a vault,
-    // upgrade authority, or other.
-    pub fn create_transaction(ctx: Context<CreateTransaction>, authority_index: u32) -> Result<()> {
+    // instructions back into this program (ie: config changes for self-governed
+    // multisigs), whereas authorities 1 or greater refer to a vault,
+    // upgrade authority, or other. an optional expiry can be set, after which
+    // the transaction can no longer be voted on or executed.
+    pub fn create_transaction(ctx: Context<CreateTransaction>, authority_index: u32, expiry: Option<MsExpiry>) -> Result<()> {
         let ms = &mut ctx.accounts.multisig;
         let authority_bump =  {
                 let (_, auth_bump) = Pubkey::find_program_address(&[
@@ -198,6 +617,7 @@ pub mod mesh {
         };
 
         ms.transaction_index =  ms.transaction_index.checked_add(1).unwrap();
+        ms.record_transaction_account()?;
         ctx.accounts.transaction.init(
             ctx.accounts.creator.key(),
             ms.key(),
@@ -205,13 +625,18 @@ pub mod mesh {
             *ctx.bumps.get("transaction").unwrap(),
             authority_index,
             authority_bump,
-        )
-
+        )?;
+        ctx.accounts.transaction.set_expiry(expiry)
     }
 
     // instruction to set the state of a transaction "active"
     // "active" transactions can then be signed off by multisig members
-    pub fn activate_transaction(ctx: Context<ActivateTransaction>) -> Result<()> {
+    // if an expiry is provided, it replaces the one set when creating the transaction
+    pub fn activate_transaction(ctx: Context<ActivateTransaction>, expiry: Option<MsExpiry>) -> Result<()> {
+        if expiry.is_some() {
+            ctx.accounts.transaction.set_expiry(expiry)?;
+        }
+        ctx.accounts.multisig.open_transaction()?;
         ctx.accounts.transaction.activate()
     }
 
@@ -221,28 +646,15 @@ pub mod mesh {
     // authority specified during the transaction creation
     pub fn add_instruction(ctx: Context<AddInstruction>, incoming_instruction: IncomingInstruction, authority_index: Option<u32>, authority_bump: Option<u8>, authority_type: MsAuthorityType) -> Result<()> {
         let tx = &mut ctx.accounts.transaction;
-        
-        let mut ix_authority_index = authority_index;
-        let mut ix_authority_bump = authority_bump;
-        let mut ix_authority_type = authority_type;
-
-        // check the proper authority level option is set
-        if ix_authority_type != MsAuthorityType::Default && ix_authority_type != MsAuthorityType::Custom{
-            return err!(GraphsError::InvalidAuthorityType);
-        }
-
-        // if no authority values are passed in, regardless of what the authority type is,
-        // we will use the authority specified in the transaction and set the type to Default
-        if authority_index.is_none() && authority_bump.is_none() {
-            ix_authority_index = Some(tx.authority_index);
-            ix_authority_bump = Some(tx.authority_bump);
-            ix_authority_type = MsAuthorityType::Default;
-        }
-
-        // if one or the other is specified, throw an error
-        if (authority_index.is_none() && authority_bump.is_some()) || (authority_index.is_some() && authority_bump.is_none()) {
-            return err!(GraphsError::InvalidAuthorityIndex);
-        }
+        let (ix_authority_index, ix_authority_bump, ix_authority_type) = resolve_instruction_authority(
+            tx,
+            authority_index,
+            authority_bump,
+            authority_type,
+        )?;
+        check_internal_instruction(tx, &incoming_instruction, ix_authority_index, &ix_authority_type, ctx.program_id)?;
+        // the transaction needs the approvals required by every vault it signs with
+        tx.add_signing_authority(ix_authority_index, &ix_authority_type)?;
 
         tx.instruction_index = tx.instruction_index.checked_add(1).unwrap();
         ctx.accounts.instruction.init(
@@ -252,63 +664,265 @@ pub mod mesh {
             ix_authority_index,
             ix_authority_bump,
             ix_authority_type,
+        )?;
+
+        let needed_len = ctx.accounts.transaction.size();
+        grow_account(
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &Rent::get()?,
+            needed_len,
         )
     }
 
+    // instruction to create a transaction and attach all of its instructions at once
+    // the instruction accounts are allocated from the remaining accounts, in order, and
+    // the transaction can optionally be activated and approved by the creator
+    pub fn create_transaction_with_instructions<'info>(
+        ctx: Context<'_,'_,'_,'info, CreateTransaction<'info>>,
+        authority_index: u32,
+        expiry: Option<MsExpiry>,
+        instructions: Vec<IncomingInstructionArgs>,
+        activate: bool,
+        approve: bool,
+    ) -> Result<()> {
+        // only active transactions can be approved
+        if approve && !activate {
+            return err!(GraphsError::InvalidTransactionState);
+        }
+        if ctx.remaining_accounts.len() != instructions.len() {
+            return err!(GraphsError::InvalidNumberOfAccounts);
+        }
+
+        create_transaction(
+            Context::new(
+                ctx.program_id,
+                ctx.accounts,
+                ctx.remaining_accounts,
+                ctx.bumps.clone()
+            ), authority_index, expiry
+        )?;
+
+        let rent = Rent::get()?;
+        let tx_key = ctx.accounts.transaction.key();
+        for (args, ix_account) in instructions.into_iter().zip(ctx.remaining_accounts.iter()) {
+            let tx = &mut ctx.accounts.transaction;
+            let (ix_authority_index, ix_authority_bump, ix_authority_type) = resolve_instruction_authority(
+                tx,
+                args.authority_index,
+                args.authority_bump,
+                args.authority_type,
+            )?;
+            check_internal_instruction(tx, &args.instruction, ix_authority_index, &ix_authority_type, ctx.program_id)?;
+            tx.add_signing_authority(ix_authority_index, &ix_authority_type)?;
+
+            let space = 8 + args.instruction.get_max_size();
+            if space > MsInstruction::MAXIMUM_SIZE {
+                return err!(GraphsError::InvalidTransactionState);
+            }
+
+            // the instruction account must be the pda for the next instruction index
+            tx.instruction_index = tx.instruction_index.checked_add(1).unwrap();
+            let instruction_index = tx.instruction_index.to_le_bytes();
+            let (ix_pda, ix_bump) = Pubkey::find_program_address(&[
+                b"squad",
+                tx_key.as_ref(),
+                &instruction_index,
+                b"instruction"],
+                ctx.program_id
+            );
+            if &ix_pda != ix_account.key {
+                return err!(GraphsError::InvalidInstructionAccount);
+            }
+
+            create_pda_account(
+                ix_account,
+                &ctx.accounts.creator.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &rent,
+                space,
+                &[
+                    b"squad",
+                    tx_key.as_ref(),
+                    &instruction_index,
+                    b"instruction",
+                    &[ix_bump]
+                ],
+            )?;
+
+            let mut ms_ix: Account<MsInstruction> = Account::try_from_unchecked(ix_account)?;
+            ms_ix.init(
+                tx.instruction_index,
+                args.instruction,
+                ix_bump,
+                ix_authority_index,
+                ix_authority_bump,
+                ix_authority_type,
+            )?;
+            ms_ix.exit(ctx.program_id)?;
+        }
+
+        // make room for the tracked signing authorities
+        let needed_len = ctx.accounts.transaction.size();
+        grow_account(
+            &ctx.accounts.transaction.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &rent,
+            needed_len,
+        )?;
+
+        if activate {
+            ctx.accounts.multisig.open_transaction()?;
+            ctx.accounts.transaction.activate()?;
+        }
+
+        if approve {
+            if !ctx.accounts.multisig.has_permission(ctx.accounts.creator.key(), Ms::PERMISSION_VOTE) {
+                return err!(GraphsError::MissingPermission);
+            }
+            cast_approval(&ctx.accounts.multisig, &mut ctx.accounts.transaction, ctx.accounts.creator.key())?;
+            grow_transaction(
+                &ctx.accounts.transaction,
+                &ctx.accounts.creator.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+        }
+        Ok(())
+    }
+
     // instruction to approve a transaction on behalf of a member
     // the transaction must have an "active" status
     pub fn approve_transaction(ctx: Context<VoteTransaction>) -> Result<()> {
-        // if they have previously voted to reject, remove that item (change vote check)
-        if let Some(ind) = ctx.accounts.transaction.has_voted_reject(ctx.accounts.member.key()) { ctx.accounts.transaction.remove_reject(ind)?; }
+        cast_approval(&ctx.accounts.multisig, &mut ctx.accounts.transaction, ctx.accounts.member.key())?;
+        grow_transaction(
+            &ctx.accounts.transaction,
+            &ctx.accounts.member.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
+    }
 
-        // if they haven't already approved
-        if ctx.accounts.transaction.has_voted_approve(ctx.accounts.member.key()).is_none() { ctx.accounts.transaction.sign(ctx.accounts.member.key())?; }
+    // instruction to approve a transaction on behalf of several members at once, using
+    // off-chain signatures. each signature must be verified by an ed25519 program instruction
+    // placed before this one, over the message built by approval_message. signatures over any
+    // other message are ignored, but a signer without vote permission fails the whole batch
+    // rather than being skipped, so a relayer never lands a partial batch without noticing
+    // the transaction must have an "active" status
+    pub fn approve_with_signatures(ctx: Context<ApproveWithSignatures>) -> Result<()> {
+        let message = approval_message(
+            &ctx.accounts.multisig.key(),
+            &ctx.accounts.transaction.key(),
+            ctx.accounts.transaction.transaction_index,
+        );
+        let signers = ed25519_signers(&ctx.accounts.instructions, &message)?;
+        if signers.is_empty() {
+            return err!(GraphsError::InvalidSignatures);
+        }
 
-        // if current number of signers reaches threshold, mark the transaction as execute ready
-        if ctx.accounts.transaction.approved.len() >= usize::from(ctx.accounts.multisig.threshold) {
-            ctx.accounts.transaction.ready_to_execute()?;
+        for member in signers {
+            if !ctx.accounts.multisig.has_permission(member, Ms::PERMISSION_VOTE) {
+                return err!(GraphsError::MissingPermission);
+            }
+            // the signed message has no nonce, so it could be replayed after the member
+            // voted on-chain. a signature never replaces a vote that's already recorded
+            if ctx.accounts.transaction.has_voted(member) {
+                continue;
+            }
+            cast_approval(&ctx.accounts.multisig, &mut ctx.accounts.transaction, member)?;
         }
-        Ok(())
+        grow_transaction(
+            &ctx.accounts.transaction,
+            &ctx.accounts.relayer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
     }
 
     // instruction to reject a transaction
     // the transaction must have an "active" status
     pub fn reject_transaction(ctx: Context<VoteTransaction>) -> Result<()> {
-        // if they have previously voted to approve, remove that item (change vote check)
-        if let Some(ind) = ctx.accounts.transaction.has_voted_approve(ctx.accounts.member.key()) { ctx.accounts.transaction.remove_approve(ind)?; }
+        let member = ctx.accounts.member.key();
+        cast_rejection(&mut ctx.accounts.multisig, &mut ctx.accounts.transaction, member)?;
+        grow_transaction(
+            &ctx.accounts.transaction,
+            &ctx.accounts.member.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
+    }
 
-        // check if they haven't already voted reject
-        if ctx.accounts.transaction.has_voted_reject(ctx.accounts.member.key()).is_none() { ctx.accounts.transaction.reject(ctx.accounts.member.key())?; }
+    // instruction for a member to delegate their vote to another key, optionally
+    // until an expiry timestamp. the delegate can approve or reject on their behalf
+    pub fn create_delegation(ctx: Context<CreateDelegation>, delegate: Pubkey, expiry: Option<i64>) -> Result<()> {
+        ctx.accounts.delegation.init(
+            ctx.accounts.multisig.key(),
+            ctx.accounts.member.key(),
+            delegate,
+            expiry,
+            *ctx.bumps.get("delegation").unwrap(),
+        )
+    }
 
-        // ie total members 7, threshold 3, cutoff = 4
-        // ie total member 8, threshold 6, cutoff = 2
-        let cutoff = ctx.accounts.multisig.keys.len().checked_sub(usize::from(ctx.accounts.multisig.threshold)).unwrap();
-        if ctx.accounts.transaction.rejected.len() > cutoff {
-            ctx.accounts.transaction.set_rejected()?;
-        }
+    // instruction for a member to remove their delegation, returning the rent
+    pub fn remove_delegation(_ctx: Context<RemoveDelegation>) -> Result<()> {
         Ok(())
     }
 
+    // instruction for a delegate to approve a transaction on behalf of a member
+    // the approval is recorded for the member
+    pub fn delegate_approve_transaction(ctx: Context<DelegateVoteTransaction>) -> Result<()> {
+        let member = ctx.accounts.delegation.member;
+        cast_approval(&ctx.accounts.multisig, &mut ctx.accounts.transaction, member)?;
+        grow_transaction(
+            &ctx.accounts.transaction,
+            &ctx.accounts.delegate.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
+    }
+
+    // instruction for a delegate to reject a transaction on behalf of a member
+    // the rejection is recorded for the member
+    pub fn delegate_reject_transaction(ctx: Context<DelegateVoteTransaction>) -> Result<()> {
+        let member = ctx.accounts.delegation.member;
+        cast_rejection(&mut ctx.accounts.multisig, &mut ctx.accounts.transaction, member)?;
+        grow_transaction(
+            &ctx.accounts.transaction,
+            &ctx.accounts.delegate.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
+    }
+
     // instruction to cancel a transaction
     // transactions must be in the "executeReady" status
     pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
         // check if they haven't cancelled yet
         if ctx.accounts.transaction.has_cancelled(ctx.accounts.member.key()).is_none() { ctx.accounts.transaction.cancel(ctx.accounts.member.key())? }
 
-        // if the current number of signers reaches threshold, mark the transaction as "cancelled"
-        if ctx.accounts.transaction.cancelled.len() >= usize::from(ctx.accounts.multisig.threshold) {
+        // if the weight of the signers reaches the cancel threshold, mark the transaction as "cancelled"
+        let cancel_threshold = ctx.accounts.multisig.cancel_threshold_for(&ctx.accounts.transaction);
+        if ctx.accounts.multisig.tally(&ctx.accounts.transaction.cancelled) >= cancel_threshold {
             ctx.accounts.transaction.set_cancelled()?;
+            ctx.accounts.multisig.settle_transaction()?;
         }
-        Ok(())
+        grow_transaction(
+            &ctx.accounts.transaction,
+            &ctx.accounts.member.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
     }
 
     // instruction to execute a transaction
     // transaction status must be "executeReady"
     pub fn execute_transaction<'info>(ctx: Context<'_,'_,'_,'info,ExecuteTransaction<'info>>, account_list: Vec<u8>) -> Result<()> {
+        // if members changed since it was approved, it goes back to voting when short of the threshold
+        if !retally_transaction(&ctx.accounts.multisig, &mut ctx.accounts.transaction)? {
+            return Ok(());
+        }
+
         // check that we are provided at least one instruction
         if ctx.accounts.transaction.instruction_index < 1 {
             // if no instructions were found, mark it as executed and move on
             ctx.accounts.transaction.set_executed()?;
+            ctx.accounts.multisig.settle_transaction()?;
             return Ok(());
         }
 
@@ -428,12 +1042,18 @@ pub mod mesh {
         ctx.accounts.transaction.set_executed()?;
         // reload any multisig changes
         ctx.accounts.multisig.reload()?;
+        ctx.accounts.multisig.settle_transaction()?;
         Ok(())
     }
 
     // instruction to sequentially execute parts of a transaction
     // instructions executed in this matter must be executed in order
     pub fn execute_instruction<'info>(ctx: Context<'_,'_,'_,'info,ExecuteInstruction<'info>>) -> Result<()> {
+        // re-tally before the first instruction runs, a partially executed transaction is kept going
+        if ctx.accounts.transaction.executed_index < 1 && !retally_transaction(&ctx.accounts.multisig, &mut ctx.accounts.transaction)? {
+            return Ok(());
+        }
+
         let ms_key = &ctx.accounts.multisig.key();
         let ms_ix = &mut ctx.accounts.instruction;
         let tx = &mut ctx.accounts.transaction;
@@ -520,58 +1140,310 @@ pub mod mesh {
         ms_ix.set_executed()?;
         // set the executed index to match
         tx.executed_index = ms_ix.instruction_index;
+        // reload any multisig changes
+        ctx.accounts.multisig.reload()?;
         // this is the last instruction - set the transaction as executed
         if ctx.accounts.instruction.instruction_index == ctx.accounts.transaction.instruction_index {
             ctx.accounts.transaction.set_executed()?;
+            ctx.accounts.multisig.settle_transaction()?;
         }
-        // reload any multisig changes
-        ctx.accounts.multisig.reload()?;
         Ok(())
     }
 
-    // instruction to remove a member/key from the multisig and change the threshold
+    // instruction to nominate a new external authority
+    // the handover only completes once the nominated key accepts it
     pub fn change_external_authority<'info>(
         ctx: Context<MsAuth<'info>>, new_authority: Pubkey
     ) -> Result<()> {
         let ms = &mut ctx.accounts.multisig;
-        ms.external_authority = new_authority;
+        ms.pending_external_authority = Some(new_authority);
         Ok(())
     }
-    
-}
 
-#[derive(Accounts)]
-#[instruction(external_authority: Pubkey, threshold: u16, create_key: Pubkey, members: Vec<Pubkey>)]
-pub struct Create<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = Ms::SIZE_WITHOUT_MEMBERS + (members.len() * 32),
-        seeds = [b"squad", create_key.as_ref(), b"multisig"], bump
-    )]
-    pub multisig: Account<'info, Ms>,
-
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    pub system_program: Program<'info, System>
-}
+    // instruction to permanently give up the external authority, freezing the multisig's configuration
+    pub fn renounce_external_authority(ctx: Context<MsAuth>) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        ms.external_authority = Ms::RENOUNCED_AUTHORITY;
+        ms.pending_external_authority = None;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct CreateTransaction<'info> {
-    #[account(
-        mut,
-        seeds = [
-            b"squad",
-            multisig.create_key.as_ref(),
-            b"multisig"
-        ],
-        bump = multisig.bump,
-        constraint = multisig.is_member(creator.key()).is_some() @GraphsError::KeyNotInMultisig,
-    )]
-    pub multisig: Account<'info, Ms>,
+    // instruction for the nominated external authority to accept the handover
+    pub fn accept_external_authority(ctx: Context<AcceptExternalAuthority>) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        ms.external_authority = ctx.accounts.new_authority.key();
+        ms.pending_external_authority = None;
+        Ok(())
+    }
 
-    #[account(
-        init,
+    // instruction for the current external authority to withdraw a nomination
+    pub fn cancel_external_authority_change(ctx: Context<MsAuth>) -> Result<()> {
+        let ms = &mut ctx.accounts.multisig;
+        if ms.pending_external_authority.is_none() {
+            return err!(GraphsError::InvalidExternalAuthority);
+        }
+        ms.pending_external_authority = None;
+        Ok(())
+    }
+
+    // instruction for a guardian to veto an active or execute ready transaction that hasn't started executing
+    pub fn veto_transaction(ctx: Context<VetoTransaction>) -> Result<()> {
+        ctx.accounts.multisig.settle_transaction()?;
+        ctx.accounts.transaction.set_vetoed()
+    }
+
+    // instruction to mark a transaction that has passed its expiry as expired
+    // this can be called by anyone, but not on a partly executed transaction
+    pub fn expire_transaction(ctx: Context<ExpireTransaction>) -> Result<()> {
+        if ctx.accounts.transaction.status != MsTransactionStatus::Draft {
+            ctx.accounts.multisig.settle_transaction()?;
+        }
+        ctx.accounts.transaction.set_expired()
+    }
+
+    // instruction to mark a draft or active transaction that was deprecated by a
+    // config change (ms_change_index) as stale. this can be called by anyone
+    pub fn mark_stale(ctx: Context<MarkStale>) -> Result<()> {
+        if ctx.accounts.transaction.status == MsTransactionStatus::Active {
+            ctx.accounts.multisig.settle_transaction()?;
+        }
+        ctx.accounts.transaction.set_stale()
+    }
+
+    // instruction to close a finished transaction and return the rent to its creator
+    // transactions must be executed, rejected, cancelled, or deprecated by a config change.
+    // transactions with attached instructions must use close_transaction_with_instructions
+    pub fn close_transaction(ctx: Context<CloseTransaction>) -> Result<()> {
+        if ctx.accounts.transaction.instruction_index > 0 {
+            return err!(GraphsError::InvalidNumberOfAccounts);
+        }
+        // deprecated active transactions are still tracked as open
+        if ctx.accounts.transaction.status == MsTransactionStatus::Active {
+            ctx.accounts.multisig.settle_transaction()?;
+        }
+        ctx.accounts.multisig.release_transaction_account()
+    }
+
+    // instruction to close a finished transaction along with its instruction accounts
+    // every attached instruction account must be passed in the remaining accounts, in order
+    pub fn close_transaction_with_instructions<'info>(ctx: Context<'_,'_,'_,'info, CloseTransaction<'info>>) -> Result<()> {
+        // deprecated active transactions are still tracked as open
+        if ctx.accounts.transaction.status == MsTransactionStatus::Active {
+            ctx.accounts.multisig.settle_transaction()?;
+        }
+        ctx.accounts.multisig.release_transaction_account()?;
+        close_instruction_accounts(
+            &ctx.accounts.transaction,
+            ctx.remaining_accounts,
+            ctx.accounts.creator.to_account_info(),
+        )
+    }
+
+    // instruction for the creator to withdraw their own draft or active transaction
+    // before anyone has approved it. the transaction is marked as cancelled, and can
+    // optionally be closed along with the instruction accounts passed in the remaining accounts
+    pub fn withdraw_transaction<'info>(ctx: Context<'_,'_,'_,'info, WithdrawTransaction<'info>>, close: bool) -> Result<()> {
+        if ctx.accounts.transaction.status == MsTransactionStatus::Active {
+            ctx.accounts.multisig.settle_transaction()?;
+        }
+        ctx.accounts.transaction.set_cancelled()?;
+
+        if close {
+            ctx.accounts.multisig.release_transaction_account()?;
+            close_instruction_accounts(
+                &ctx.accounts.transaction,
+                ctx.remaining_accounts,
+                ctx.accounts.creator.to_account_info(),
+            )?;
+            ctx.accounts.transaction.close(ctx.accounts.creator.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // instruction to create a spending limit, allowing the listed members to transfer
+    // up to the amount each period from an authority (vault) without a transaction vote.
+    // the mint is Pubkey::default() for native SOL
+    pub fn create_spending_limit(
+        ctx: Context<CreateSpendingLimit>, authority_index: u32, mint: Pubkey, amount: u64, period: SpendingLimitPeriod, members: Vec<Pubkey>
+    ) -> Result<()> {
+        // authority 0 is reserved for internal instructions
+        if authority_index < 1 {
+            return err!(GraphsError::InvalidAuthorityIndex);
+        }
+
+        let mut members = members;
+        members.sort();
+        members.dedup();
+        if members.is_empty() {
+            return err!(GraphsError::EmptyMembers);
+        }
+        if members.iter().any(|&m| ctx.accounts.multisig.is_member(m).is_none()) {
+            return err!(GraphsError::KeyNotInMultisig);
+        }
+
+        ctx.accounts.spending_limit.init(
+            ctx.accounts.multisig.key(),
+            authority_index,
+            mint,
+            members,
+            *ctx.bumps.get("spending_limit").unwrap(),
+        )?;
+        ctx.accounts.multisig.record_spending_limit()?;
+        ctx.accounts.spending_limit.set_allowance(amount, period)
+    }
+
+    // instruction to remove a spending limit, returning the rent to the external authority
+    pub fn remove_spending_limit(ctx: Context<RemoveSpendingLimit>) -> Result<()> {
+        ctx.accounts.multisig.release_spending_limit()
+    }
+
+    // instruction for a member listed on a spending limit to transfer directly from the
+    // authority (vault). for native SOL the source is the vault itself, for tokens it's
+    // a token account owned by the vault
+    pub fn use_spending_limit(ctx: Context<UseSpendingLimit>, amount: u64) -> Result<()> {
+        let spending_limit = &mut ctx.accounts.spending_limit;
+        spending_limit.reset_if_needed(Clock::get()?.unix_timestamp)?;
+        if spending_limit.spend(amount).is_none() {
+            return err!(GraphsError::SpendingLimitExceeded);
+        }
+
+        let ms_key = ctx.accounts.multisig.key();
+        let authority_index = spending_limit.authority_index.to_le_bytes();
+        let vault_bump = *ctx.bumps.get("vault").unwrap();
+        let vault_seeds: &[&[u8]] = &[
+            b"squad",
+            ms_key.as_ref(),
+            &authority_index,
+            b"authority",
+            &[vault_bump]
+        ];
+
+        if spending_limit.is_native() {
+            if ctx.accounts.source.key() != ctx.accounts.vault.key() {
+                return err!(GraphsError::InvalidInstructionAccount);
+            }
+            invoke_signed(
+                &transfer(ctx.accounts.vault.key, ctx.accounts.destination.key, amount),
+                &[
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.destination.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[vault_seeds]
+            )?;
+        } else {
+            // the source must hold the spending limit mint, otherwise any token could be moved
+            let (source_mint, source_owner) = read_token_account(&ctx.accounts.source)?;
+            if source_mint != spending_limit.mint || source_owner != ctx.accounts.vault.key() {
+                return err!(GraphsError::InvalidInstructionAccount);
+            }
+            if ctx.accounts.token_program.key() != token_program::ID {
+                return err!(GraphsError::InvalidInstructionAccount);
+            }
+            invoke_signed(
+                &token_transfer_instruction(ctx.accounts.source.key, ctx.accounts.destination.key, ctx.accounts.vault.key, amount),
+                &[
+                    ctx.accounts.source.to_account_info(),
+                    ctx.accounts.destination.to_account_info(),
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                ],
+                &[vault_seeds]
+            )?;
+        }
+        Ok(())
+    }
+
+    // instruction to create the metadata account describing the multisig
+    pub fn create_metadata(ctx: Context<CreateMetadata>, name: String, description: String, uri: String, tags: Vec<MetadataTag>) -> Result<()> {
+        if !MultisigMetadata::is_valid(&name, &description, &uri, &tags) {
+            return err!(GraphsError::InvalidMetadata);
+        }
+        ctx.accounts.metadata.init(
+            ctx.accounts.multisig.key(),
+            name,
+            description,
+            uri,
+            tags,
+            *ctx.bumps.get("metadata").unwrap(),
+        )
+    }
+
+    // instruction to replace the multisig metadata, resizing the account to fit
+    pub fn update_metadata(ctx: Context<UpdateMetadata>, name: String, description: String, uri: String, tags: Vec<MetadataTag>) -> Result<()> {
+        if !MultisigMetadata::is_valid(&name, &description, &uri, &tags) {
+            return err!(GraphsError::InvalidMetadata);
+        }
+        ctx.accounts.metadata.update(name, description, uri, tags)?;
+
+        let metadata_account_info = ctx.accounts.metadata.to_account_info();
+        let needed_len = ctx.accounts.metadata.size();
+        if metadata_account_info.data_len() < needed_len {
+            grow_account(
+                &metadata_account_info,
+                &ctx.accounts.external_authority.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent,
+                needed_len,
+            )
+        } else {
+            shrink_account(
+                &metadata_account_info,
+                &ctx.accounts.external_authority.to_account_info(),
+                needed_len,
+            )
+        }
+    }
+
+    // instruction to decommission a multisig, refunding its rent to the receiver
+    // every transaction account must be closed and every spending limit removed first, so
+    // nothing is left behind for a multisig re-created at the same address. Any funds
+    // held by the multisig authorities (vaults) are not touched and should be moved beforehand.
+    // the address can only be reused by the create key signing a new create, which brings back
+    // the same vaults
+    // self-governed multisigs can't be closed: the closing transaction would itself still be
+    // open while it executes. hand the external authority to a key first to close one
+    pub fn close_multisig(_ctx: Context<CloseMultisig>) -> Result<()> {
+        Ok(())
+    }
+    
+}
+
+#[derive(Accounts)]
+#[instruction(external_authority: Option<Pubkey>, threshold: u16, members: Vec<Pubkey>, weights: Vec<u16>)]
+pub struct Create<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = Ms::SIZE_WITHOUT_MEMBERS + (members.len() * Ms::MEMBER_SIZE),
+        seeds = [b"squad", create_key.key().as_ref(), b"multisig"], bump
+    )]
+    pub multisig: Account<'info, Ms>,
+
+    // key used to seed the multisig, signs so a closed multisig can't be re-created by anyone else
+    pub create_key: Signer<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct CreateTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = multisig.has_permission(creator.key(), Ms::PERMISSION_INITIATE) @GraphsError::MissingPermission,
+    )]
+    pub multisig: Account<'info, Ms>,
+
+    #[account(
+        init,
         payer = creator,
         space = 8 + MsTransaction::initial_size_with_members(multisig.keys.len()),
         seeds = [
@@ -598,7 +1470,7 @@ pub struct AddInstruction<'info> {
             b"multisig"
         ],
         bump = multisig.bump,
-        constraint = multisig.is_member(creator.key()).is_some() @GraphsError::KeyNotInMultisig,
+        constraint = multisig.has_permission(creator.key(), Ms::PERMISSION_INITIATE) @GraphsError::MissingPermission,
     )]
     pub multisig: Account<'info, Ms>,
 
@@ -638,13 +1510,14 @@ pub struct AddInstruction<'info> {
 #[derive(Accounts)]
 pub struct ActivateTransaction<'info> {
     #[account(
+        mut,
         seeds = [
             b"squad",
             multisig.create_key.as_ref(),
             b"multisig"
         ],
         bump = multisig.bump,
-        constraint = multisig.is_member(creator.key()).is_some() @GraphsError::KeyNotInMultisig,
+        constraint = multisig.has_permission(creator.key(), Ms::PERMISSION_INITIATE) @GraphsError::MissingPermission,
     )]
     pub multisig: Account<'info, Ms>,
 
@@ -671,13 +1544,14 @@ pub struct ActivateTransaction<'info> {
 #[derive(Accounts)]
 pub struct VoteTransaction<'info> {
     #[account(
+        mut,
         seeds = [
             b"squad",
             multisig.create_key.as_ref(),
             b"multisig"
         ],
         bump = multisig.bump,
-        constraint = multisig.is_member(member.key()).is_some() @GraphsError::KeyNotInMultisig,
+        constraint = multisig.has_permission(member.key(), Ms::PERMISSION_VOTE) @GraphsError::MissingPermission,
     )]
     pub multisig: Account<'info, Ms>,
 
@@ -692,6 +1566,7 @@ pub struct VoteTransaction<'info> {
         constraint = transaction.status == MsTransactionStatus::Active @GraphsError::InvalidTransactionState,
         constraint = transaction.transaction_index > multisig.ms_change_index @GraphsError::DeprecatedTransaction,
         constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+        constraint = !transaction.is_expired() @GraphsError::TransactionExpired,
     )]
     pub transaction: Account<'info, MsTransaction>,
 
@@ -700,6 +1575,153 @@ pub struct VoteTransaction<'info> {
     pub system_program: Program<'info, System>
 }
 
+#[derive(Accounts)]
+pub struct CreateDelegation<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = multisig.has_permission(member.key(), Ms::PERMISSION_VOTE) @GraphsError::MissingPermission,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        init,
+        payer = member,
+        space = Delegation::SIZE,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            member.key().as_ref(),
+            b"delegation"
+        ], bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct RemoveDelegation<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            member.key().as_ref(),
+            b"delegation"
+        ], bump = delegation.bump,
+        close = member,
+        constraint = delegation.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    // the member doesn't need to still be in the multisig to remove their delegation
+    #[account(mut)]
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DelegateVoteTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        // the represented member must still be able to vote
+        constraint = multisig.has_permission(delegation.member, Ms::PERMISSION_VOTE) @GraphsError::MissingPermission,
+    )]
+    pub multisig: Account<'info, Ms>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        constraint = transaction.status == MsTransactionStatus::Active @GraphsError::InvalidTransactionState,
+        constraint = transaction.transaction_index > multisig.ms_change_index @GraphsError::DeprecatedTransaction,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+        constraint = !transaction.is_expired() @GraphsError::TransactionExpired,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            delegation.member.as_ref(),
+            b"delegation"
+        ], bump = delegation.bump,
+        constraint = delegation.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+        constraint = delegation.delegate == delegate.key() @GraphsError::InvalidDelegation,
+        constraint = delegation.is_active() @GraphsError::InvalidDelegation,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(mut)]
+    pub delegate: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ApproveWithSignatures<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Ms>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        constraint = transaction.status == MsTransactionStatus::Active @GraphsError::InvalidTransactionState,
+        constraint = transaction.transaction_index > multisig.ms_change_index @GraphsError::DeprecatedTransaction,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+        constraint = !transaction.is_expired() @GraphsError::TransactionExpired,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+
+    /// CHECK: the instructions sysvar, used to read the ed25519 program instructions
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    // anyone can relay the signatures
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
 #[derive(Accounts)]
 pub struct CancelTransaction<'info> {
     #[account(
@@ -710,7 +1732,7 @@ pub struct CancelTransaction<'info> {
             b"multisig"
         ],
         bump = multisig.bump,
-        constraint = multisig.is_member(member.key()).is_some() @GraphsError::KeyNotInMultisig,
+        constraint = multisig.has_permission(member.key(), Ms::PERMISSION_VOTE) @GraphsError::MissingPermission,
     )]
     pub multisig: Account<'info, Ms>,
 
@@ -742,8 +1764,8 @@ pub struct ExecuteTransaction<'info> {
             b"multisig"
         ],
         bump = multisig.bump,
-        // only members can execute unless specified by the allow_external_execute setting
-        constraint = multisig.is_member(member.key()).is_some() || multisig.allow_external_execute @GraphsError::KeyNotInMultisig,
+        // only members with the execute permission can execute unless specified by the allow_external_execute setting
+        constraint = multisig.has_permission(member.key(), Ms::PERMISSION_EXECUTE) || multisig.allow_external_execute @GraphsError::MissingPermission,
     )]
     pub multisig: Box<Account<'info, Ms>>,
 
@@ -759,6 +1781,8 @@ pub struct ExecuteTransaction<'info> {
         constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
         // if they've already started sequential execution, they must continue
         constraint = transaction.executed_index < 1 @GraphsError::PartialExecution,
+        constraint = transaction.time_lock_elapsed(multisig.time_lock_seconds) @GraphsError::TimeLockNotElapsed,
+        constraint = !transaction.is_expired() @GraphsError::TransactionExpired,
     )]
     pub transaction: Account<'info, MsTransaction>,
 
@@ -777,7 +1801,7 @@ pub struct ExecuteInstruction<'info> {
             b"multisig"
         ],
         bump = multisig.bump,
-        constraint = multisig.is_member(member.key()).is_some() || multisig.allow_external_execute @GraphsError::KeyNotInMultisig,
+        constraint = multisig.has_permission(member.key(), Ms::PERMISSION_EXECUTE) || multisig.allow_external_execute @GraphsError::MissingPermission,
     )]
     pub multisig: Box<Account<'info, Ms>>,
 
@@ -791,6 +1815,9 @@ pub struct ExecuteInstruction<'info> {
         ], bump = transaction.bump,
         constraint = transaction.status == MsTransactionStatus::ExecuteReady @GraphsError::InvalidTransactionState,
         constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+        constraint = transaction.time_lock_elapsed(multisig.time_lock_seconds) @GraphsError::TimeLockNotElapsed,
+        // once execution has started, the remaining instructions can still run after the expiry
+        constraint = transaction.executed_index > 0 || !transaction.is_expired() @GraphsError::TransactionExpired,
     )]
     pub transaction: Account<'info, MsTransaction>,
     
@@ -812,6 +1839,623 @@ pub struct ExecuteInstruction<'info> {
     pub member: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct VetoTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = multisig.is_guardian(guardian.key()).is_some() @GraphsError::InvalidGuardian,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        constraint = transaction.status == MsTransactionStatus::Active || transaction.status == MsTransactionStatus::ExecuteReady @GraphsError::InvalidTransactionState,
+        // a partly executed transaction can't be stopped halfway
+        constraint = transaction.executed_index < 1 @GraphsError::InvalidTransactionState,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        constraint = transaction.status == MsTransactionStatus::Draft ||
+            transaction.status == MsTransactionStatus::Active ||
+            transaction.status == MsTransactionStatus::ExecuteReady @GraphsError::InvalidTransactionState,
+        constraint = transaction.is_expired() @GraphsError::TransactionNotExpired,
+        // a partly executed transaction has to be allowed to finish
+        constraint = transaction.executed_index < 1 @GraphsError::InvalidTransactionState,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+}
+
+#[derive(Accounts)]
+pub struct MarkStale<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        constraint = transaction.status == MsTransactionStatus::Draft || transaction.status == MsTransactionStatus::Active @GraphsError::InvalidTransactionState,
+        constraint = transaction.transaction_index <= multisig.ms_change_index @GraphsError::TransactionNotStale,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        close = creator,
+        constraint = transaction.is_closeable(multisig.ms_change_index) @GraphsError::InvalidTransactionState,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+
+    /// CHECK: the original creator of the transaction, who paid the rent
+    #[account(
+        mut,
+        constraint = creator.key() == transaction.creator @GraphsError::InvalidInstructionAccount,
+    )]
+    pub creator: AccountInfo<'info>,
+
+    // members or the creator can close the transaction
+    #[account(
+        mut,
+        constraint = multisig.is_member(member.key()).is_some() || member.key() == transaction.creator @GraphsError::KeyNotInMultisig,
+    )]
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &transaction.transaction_index.to_le_bytes(),
+            b"transaction"
+        ], bump = transaction.bump,
+        constraint = creator.key() == transaction.creator @GraphsError::InvalidInstructionAccount,
+        constraint = transaction.status == MsTransactionStatus::Draft || transaction.status == MsTransactionStatus::Active @GraphsError::InvalidTransactionState,
+        // once a member has approved, the transaction can only be rejected or cancelled by vote
+        constraint = transaction.approved.is_empty() @GraphsError::InvalidTransactionState,
+        constraint = transaction.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub transaction: Account<'info, MsTransaction>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority_index: u32, mint: Pubkey, amount: u64, period: SpendingLimitPeriod, members: Vec<Pubkey>)]
+pub struct CreateSpendingLimit<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority
+    )]
+    multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        init,
+        payer = external_authority,
+        space = SpendingLimit::size_with_members(members.len()),
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &authority_index.to_le_bytes(),
+            mint.as_ref(),
+            b"spending_limit"
+        ], bump
+    )]
+    pub spending_limit: Account<'info, SpendingLimit>,
+
+    #[account(mut)]
+    pub external_authority: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct RemoveSpendingLimit<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority
+    )]
+    multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &spending_limit.authority_index.to_le_bytes(),
+            spending_limit.mint.as_ref(),
+            b"spending_limit"
+        ], bump = spending_limit.bump,
+        close = external_authority,
+        constraint = spending_limit.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub spending_limit: Account<'info, SpendingLimit>,
+
+    #[account(mut)]
+    pub external_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UseSpendingLimit<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = multisig.is_member(member.key()).is_some() @GraphsError::KeyNotInMultisig,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &spending_limit.authority_index.to_le_bytes(),
+            spending_limit.mint.as_ref(),
+            b"spending_limit"
+        ], bump = spending_limit.bump,
+        constraint = spending_limit.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+        constraint = spending_limit.is_member(member.key()) @GraphsError::MissingPermission,
+    )]
+    pub spending_limit: Account<'info, SpendingLimit>,
+
+    /// CHECK: the authority (vault) of the spending limit, checked by the seeds
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            &spending_limit.authority_index.to_le_bytes(),
+            b"authority"
+        ], bump
+    )]
+    pub vault: AccountInfo<'info>,
+
+    /// CHECK: the vault for native SOL, or a token account owned by the vault
+    #[account(mut)]
+    pub source: AccountInfo<'info>,
+
+    /// CHECK: the receiving account for native SOL, or the receiving token account
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: the token program, checked when transferring tokens
+    pub token_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        close = receiver,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority,
+        constraint = !multisig.is_self_governed(&multisig.key()) @GraphsError::SelfGovernedMultisig,
+        constraint = multisig.open_transactions == 0 @GraphsError::OpenTransactions,
+        constraint = multisig.transaction_accounts == 0 @GraphsError::UnclosedTransactions,
+        constraint = multisig.spending_limits == 0 @GraphsError::OpenSpendingLimits,
+    )]
+    multisig: Box<Account<'info, Ms>>,
+    #[account(mut)]
+    pub external_authority: Signer<'info>,
+    /// CHECK: any account chosen by the external authority to receive the rent
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestMemberRotation<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = multisig.is_member(old_member.key()).is_some() @GraphsError::InvalidMember,
+        constraint = multisig.is_member(new_member.key()).is_none() @GraphsError::InvalidMember,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        init,
+        payer = old_member,
+        space = MemberRotation::SIZE,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            old_member.key().as_ref(),
+            b"rotation"
+        ], bump
+    )]
+    pub rotation: Account<'info, MemberRotation>,
+
+    #[account(mut)]
+    pub old_member: Signer<'info>,
+    pub new_member: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ApproveMemberRotation<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority,
+        constraint = multisig.is_member(rotation.old_member).is_some() @GraphsError::InvalidMember,
+    )]
+    multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            rotation.old_member.as_ref(),
+            b"rotation"
+        ], bump = rotation.bump,
+        close = old_member,
+        constraint = rotation.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub rotation: Account<'info, MemberRotation>,
+
+    pub external_authority: Signer<'info>,
+    /// CHECK: the requesting member, receives the rent of the rotation account
+    #[account(mut, address = rotation.old_member @GraphsError::InvalidMember)]
+    pub old_member: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMemberRotation<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            old_member.key().as_ref(),
+            b"rotation"
+        ], bump = rotation.bump,
+        close = old_member,
+        constraint = rotation.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub rotation: Account<'info, MemberRotation>,
+
+    // the requester doesn't need to still be in the multisig to cancel
+    #[account(mut)]
+    pub old_member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_external_authority: Option<Pubkey>, new_members: Vec<Pubkey>)]
+pub struct InitiateRecovery<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.recovery_enabled() @GraphsError::InvalidRecovery,
+        constraint = multisig.is_recovery_key(recovery_key.key()).is_some() @GraphsError::InvalidRecoveryKey,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        init,
+        payer = recovery_key,
+        space = Recovery::size_with(new_members.len(), multisig.recovery_keys.len()),
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            b"recovery"
+        ], bump
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    #[account(mut)]
+    pub recovery_key: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ApproveRecovery<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.recovery_enabled() @GraphsError::InvalidRecovery,
+        constraint = multisig.is_recovery_key(recovery_key.key()).is_some() @GraphsError::InvalidRecoveryKey,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            b"recovery"
+        ], bump = recovery.bump,
+        constraint = recovery.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    #[account(mut)]
+    pub recovery_key: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct CancelRecovery<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ],
+        bump = multisig.bump,
+        constraint = multisig.is_member(canceller.key()).is_some() || multisig.external_authority == canceller.key() @GraphsError::InvalidMember,
+    )]
+    pub multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            b"recovery"
+        ], bump = recovery.bump,
+        close = initiator,
+        constraint = recovery.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    pub canceller: Signer<'info>,
+    /// CHECK: the recovery key that proposed the recovery, receives the rent
+    #[account(mut, address = recovery.initiator @GraphsError::InvalidRecoveryKey)]
+    pub initiator: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.recovery_enabled() @GraphsError::InvalidRecovery,
+    )]
+    multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            b"recovery"
+        ], bump = recovery.bump,
+        close = initiator,
+        constraint = recovery.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    // anyone can finalize an approved recovery, paying for any extra space needed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: the recovery key that proposed the recovery, receives the rent
+    #[account(mut, address = recovery.initiator @GraphsError::InvalidRecoveryKey)]
+    pub initiator: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, description: String, uri: String, tags: Vec<MetadataTag>)]
+pub struct CreateMetadata<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority
+    )]
+    multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        init,
+        payer = external_authority,
+        space = MultisigMetadata::size_with(&name, &description, &uri, &tags),
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            b"metadata"
+        ], bump
+    )]
+    pub metadata: Account<'info, MultisigMetadata>,
+
+    #[account(mut)]
+    pub external_authority: Signer<'info>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
+        constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority
+    )]
+    multisig: Box<Account<'info, Ms>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.key().as_ref(),
+            b"metadata"
+        ], bump = metadata.bump,
+        constraint = metadata.ms == multisig.key() @GraphsError::InvalidInstructionAccount,
+    )]
+    pub metadata: Account<'info, MultisigMetadata>,
+
+    #[account(mut)]
+    pub external_authority: Signer<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct AcceptExternalAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"squad",
+            multisig.create_key.as_ref(),
+            b"multisig"
+        ], bump = multisig.bump,
+        constraint = multisig.pending_external_authority == Some(new_authority.key()) @GraphsError::InvalidExternalAuthority
+    )]
+    multisig: Box<Account<'info, Ms>>,
+    pub new_authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct MsAuth<'info> {
     #[account(
@@ -821,6 +2465,7 @@ pub struct MsAuth<'info> {
             multisig.create_key.as_ref(),
             b"multisig"
         ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
         constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority
     )]
     multisig: Box<Account<'info, Ms>>,
@@ -837,6 +2482,7 @@ pub struct MsAuthRealloc<'info> {
             multisig.create_key.as_ref(),
             b"multisig"
         ], bump = multisig.bump,
+        constraint = !multisig.is_renounced() @GraphsError::ExternalAuthorityRenounced,
         constraint = multisig.external_authority == external_authority.key() @GraphsError::InvalidExternalAuthority
     )]
     multisig: Box<Account<'info, Ms>>,
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+// a member's vote delegated to another key
+#[account]
+pub struct Delegation {
+    pub ms: Pubkey,                     // the multisig this belongs to
+    pub member: Pubkey,                 // the member being represented, used to seed pda
+    pub delegate: Pubkey,               // the key that can vote on behalf of the member
+    pub expiry: Option<i64>,            // optional unix timestamp the delegation ends at
+    pub bump: u8,                       // bump for the seed
+}
+
+impl Delegation {
+    pub const SIZE: usize = 8 +         // Anchor discriminator
+        32 +                            // the multisig key
+        32 +                            // the member key
+        32 +                            // the delegate key
+        (1 + 8) +                       // the optional expiry
+        1;                              // bump
+
+    pub fn init(&mut self, multisig: Pubkey, member: Pubkey, delegate: Pubkey, expiry: Option<i64>, bump: u8) -> Result<()> {
+        self.ms = multisig;
+        self.member = member;
+        self.delegate = delegate;
+        self.expiry = expiry;
+        self.bump = bump;
+        Ok(())
+    }
+
+    // check if the delegation hasn't expired
+    pub fn is_active(&self) -> bool {
+        match self.expiry {
+            Some(expiry) => match Clock::get() {
+                Ok(clock) => clock.unix_timestamp < expiry,
+                Err(_) => false
+            },
+            None => true
+        }
+    }
+}
@@ -13,7 +13,23 @@ pub struct Ms {
     pub create_key: Pubkey,             // random key(or not) used to seed the multisig pda
     pub allow_external_execute: bool,   // allow non-member keys to execute txs
     pub keys: Vec<Pubkey>,              // keys of the members
-    pub external_authority: Pubkey// the external multisig authority
+    pub external_authority: Pubkey,     // the external multisig authority
+    pub open_transactions: u32,         // number of active/execute ready transactions
+    pub weights: Vec<u16>,              // voting weight of each member, same order as keys
+    pub permissions: Vec<u8>,           // permission flags of each member, same order as keys
+    pub time_lock_seconds: u32,         // delay between a transaction being approved and executable
+    pub authority_thresholds: Vec<MsAuthorityThreshold>,    // threshold overrides for specific authorities
+    pub guardians: Vec<Pubkey>,         // non-member keys that can veto pending transactions
+    pub reject_threshold: Option<u16>,  // explicit weight needed to reject, derived if not set
+    pub cancel_threshold: Option<u16>,  // explicit weight needed to cancel, threshold if not set
+    pub threshold_bps: Option<u16>,     // threshold as basis points of the total weight, replaces threshold if set
+    pub pending_external_authority: Option<Pubkey>,    // nominated external authority, awaiting acceptance
+    pub recovery_keys: Vec<Pubkey>,     // keys that can recover the multisig if the external authority is lost
+    pub recovery_threshold: u16,        // recovery key approvals needed, 0 if recovery is disabled
+    pub recovery_delay_seconds: u32,    // delay between a recovery being approved and finalized
+    pub transaction_accounts: u32,      // number of transaction accounts that haven't been closed
+    pub spending_limits: u32,           // number of spending limits that haven't been removed
+    pub member_slack: u16,              // spare member slots kept when the account shrinks after removals
 }
 
 impl Ms {
@@ -26,11 +42,53 @@ impl Ms {
     32 +        // creator
     1 +         // allow external execute
     4 +         // for vec length
-    32;       // external authority
-
-    pub fn init (&mut self, external_authority: Pubkey, threshold: u16, create_key: Pubkey, members: Vec<Pubkey>, bump: u8) -> Result<()> {
+    32 +        // external authority
+    4 +         // open transactions
+    4 +         // for weights vec length
+    4 +         // for permissions vec length
+    4 +         // time lock
+    4 +         // for authority thresholds vec length
+    4 +         // for guardians vec length
+    (1 + 2) +   // reject threshold
+    (1 + 2) +   // cancel threshold
+    (1 + 2) +   // threshold basis points
+    (1 + 32) +  // pending external authority
+    4 +         // for recovery keys vec length
+    2 +         // recovery threshold
+    4 +         // recovery delay
+    4 +         // transaction accounts
+    4 +         // spending limits
+    2;          // member slack
+
+    // authority index reserved for instructions back into this program
+    pub const INTERNAL_AUTHORITY_INDEX: u32 = 0;
+
+    // sentinel external authority for multisigs whose configuration is frozen
+    pub const RENOUNCED_AUTHORITY: Pubkey = Pubkey::new_from_array([0; 32]);
+
+    // basis points for a threshold of 100%
+    pub const MAX_THRESHOLD_BPS: u16 = 10_000;
+
+    // the space used by each member: key + weight + permissions
+    pub const MEMBER_SIZE: usize = 32 + 2 + 1;
+
+    // member permission flags
+    pub const PERMISSION_INITIATE: u8 = 1 << 0;     // create, build and activate transactions
+    pub const PERMISSION_VOTE: u8 = 1 << 1;         // approve, reject and cancel transactions
+    pub const PERMISSION_EXECUTE: u8 = 1 << 2;      // execute transactions
+    pub const PERMISSION_ALL: u8 = Ms::PERMISSION_INITIATE | Ms::PERMISSION_VOTE | Ms::PERMISSION_EXECUTE;
+
+    // number of member slots the account grows by
+    pub const MEMBER_REALLOC_STEP: usize = 10;
+
+    // spare member slots kept when shrinking, until the external authority sets its own
+    pub const DEFAULT_MEMBER_SLACK: u16 = 10;
+
+    pub fn init (&mut self, external_authority: Pubkey, threshold: u16, create_key: Pubkey, members: Vec<Pubkey>, weights: Vec<u16>, bump: u8) -> Result<()> {
         self.threshold = threshold;
+        self.permissions = vec![Ms::PERMISSION_ALL; members.len()];
         self.keys = members;
+        self.weights = weights;
         self.authority_index = 1;   // default vault is the first authority
         self.transaction_index = 0;
         self.ms_change_index= 0;
@@ -38,9 +96,48 @@ impl Ms {
         self.create_key = create_key;
         self.allow_external_execute = false;
         self.external_authority = external_authority;
+        self.open_transactions = 0;
+        self.time_lock_seconds = 0;
+        self.authority_thresholds = Vec::new();
+        self.guardians = Vec::new();
+        self.reject_threshold = None;
+        self.cancel_threshold = None;
+        self.threshold_bps = None;
+        self.pending_external_authority = None;
+        self.recovery_keys = Vec::new();
+        self.recovery_threshold = 0;
+        self.recovery_delay_seconds = 0;
+        self.transaction_accounts = 0;
+        self.spending_limits = 0;
+        self.member_slack = Ms::DEFAULT_MEMBER_SLACK;
         Ok(())
     }
 
+    // whether the external authority is the multisig's own internal authority
+    pub fn is_self_governed(&self, multisig: &Pubkey) -> bool {
+        let (internal_authority, _) = Pubkey::find_program_address(&[
+            b"squad",
+            multisig.as_ref(),
+            &Ms::INTERNAL_AUTHORITY_INDEX.to_le_bytes(),
+            b"authority"
+        ], &crate::ID);
+        self.external_authority == internal_authority
+    }
+
+    // whether the external authority has been renounced
+    pub fn is_renounced(&self) -> bool {
+        self.external_authority == Ms::RENOUNCED_AUTHORITY
+    }
+
+    // the space needed for the current members plus a number of spare member slots
+    pub fn size_with_slack(&self, slack: usize) -> usize {
+        Ms::SIZE_WITHOUT_MEMBERS +
+        ((self.keys.len() + slack) * Ms::MEMBER_SIZE) +
+        (self.authority_thresholds.len() * MsAuthorityThreshold::SIZE) +
+        (self.guardians.len() * 32) +
+        (self.recovery_keys.len() * 32)
+    }
+
     pub fn is_member(&self, member: Pubkey) -> Option<usize> {
         match self.keys.binary_search(&member) {
             Ok(ind)=> Some(ind),
@@ -48,21 +145,93 @@ impl Ms {
         }
     }
 
+    pub fn is_guardian(&self, guardian: Pubkey) -> Option<usize> {
+        self.guardians.binary_search(&guardian).ok()
+    }
+
+    pub fn add_guardian(&mut self, guardian: Pubkey) -> Result<()>{
+        if let Err(ind) = self.guardians.binary_search(&guardian) {
+            self.guardians.insert(ind, guardian);
+        }
+        Ok(())
+    }
+
+    pub fn remove_guardian(&mut self, guardian: Pubkey) -> Result<()>{
+        if let Some(ind) = self.is_guardian(guardian) {
+            self.guardians.remove(ind);
+        }
+        Ok(())
+    }
+
+    pub fn is_recovery_key(&self, recovery_key: Pubkey) -> Option<usize> {
+        self.recovery_keys.binary_search(&recovery_key).ok()
+    }
+
+    // replace the recovery configuration, expects sorted keys
+    pub fn set_recovery(&mut self, recovery_keys: Vec<Pubkey>, recovery_threshold: u16, recovery_delay_seconds: u32) -> Result<()>{
+        self.recovery_keys = recovery_keys;
+        self.recovery_threshold = recovery_threshold;
+        self.recovery_delay_seconds = recovery_delay_seconds;
+        Ok(())
+    }
+
+    // whether recovery keys have been configured
+    pub fn recovery_enabled(&self) -> bool {
+        self.recovery_threshold > 0
+    }
+
+    // check if a member holds all of the given permission flags
+    pub fn has_permission(&self, member: Pubkey, permission: u8) -> bool {
+        match self.is_member(member) {
+            Some(ind) => self.permissions[ind] & permission == permission,
+            None => false
+        }
+    }
+
+    // the voting weight of a member, zero if the key is not a member or can't vote
+    pub fn weight_of(&self, member: Pubkey) -> u32 {
+        match self.is_member(member) {
+            Some(ind) if self.permissions[ind] & Ms::PERMISSION_VOTE != 0 => u32::from(self.weights[ind]),
+            _ => 0
+        }
+    }
+
+    // the combined voting weight of all members that can vote
+    pub fn total_weight(&self) -> u32 {
+        self.keys.iter().map(|&k| self.weight_of(k)).sum()
+    }
+
+    // the combined voting weight of a set of keys, ie the approvals of a transaction
+    pub fn tally(&self, keys: &[Pubkey]) -> u32 {
+        keys.iter().map(|&k| self.weight_of(k)).sum()
+    }
+
     pub fn set_change_index(&mut self, index: u32) -> Result<()>{
         self.ms_change_index = index;
         Ok(())
     }
 
+    // record a config change, deprecating all in-flight transactions
+    // unless they should be re-tallied against the new config instead
+    pub fn record_change(&mut self, retally: bool) -> Result<()>{
+        if !retally {
+            self.ms_change_index = self.transaction_index;
+        }
+        Ok(())
+    }
+
     // bumps up the authority tracking index for easy use
     pub fn add_authority(&mut self) -> Result<()>{
         self.authority_index = self.authority_index.checked_add(1).unwrap();
         Ok(())
     }
 
-    pub fn add_member(&mut self, member: Pubkey) -> Result<()>{
-        if matches!(self.is_member(member), None) {
-            self.keys.push(member);
-            self.keys.sort();
+    // insert the member in sorted position, keeping the weights aligned with the keys
+    pub fn add_member(&mut self, member: Pubkey, weight: u16) -> Result<()>{
+        if let Err(ind) = self.keys.binary_search(&member) {
+            self.keys.insert(ind, member);
+            self.weights.insert(ind, weight);
+            self.permissions.insert(ind, Ms::PERMISSION_ALL);
         }
         Ok(())
     }
@@ -70,20 +239,192 @@ impl Ms {
     pub fn remove_member(&mut self, member: Pubkey) -> Result<()>{
         if let Some(ind) = self.is_member(member) {
             self.keys.remove(ind);
-            if self.keys.len() < usize::from(self.threshold) {
-                self.threshold = self.keys.len().try_into().unwrap();
+            self.weights.remove(ind);
+            self.permissions.remove(ind);
+            self.clamp_thresholds();
+        }
+        Ok(())
+    }
+
+    // apply a batch of membership changes and a new threshold, clamping the other thresholds once at the end
+    pub fn reconfigure(&mut self, add_members: Vec<(Pubkey, u16)>, remove_members: &[Pubkey], threshold: u16) -> Result<()>{
+        for member in remove_members {
+            if let Some(ind) = self.is_member(*member) {
+                self.keys.remove(ind);
+                self.weights.remove(ind);
+                self.permissions.remove(ind);
+            }
+        }
+        for (member, weight) in add_members {
+            self.add_member(member, weight)?;
+        }
+        self.threshold = threshold;
+        self.clamp_thresholds();
+        Ok(())
+    }
+
+    // move a member's seat to a new key, keeping its weight and permissions and the keys sorted
+    pub fn replace_member(&mut self, old_member: Pubkey, new_member: Pubkey) -> Result<()>{
+        if let Some(ind) = self.is_member(old_member) {
+            self.keys.remove(ind);
+            let weight = self.weights.remove(ind);
+            let permissions = self.permissions.remove(ind);
+            if let Err(new_ind) = self.keys.binary_search(&new_member) {
+                self.keys.insert(new_ind, new_member);
+                self.weights.insert(new_ind, weight);
+                self.permissions.insert(new_ind, permissions);
             }
         }
         Ok(())
     }
 
+    // install a new member set with full permissions, expects sorted keys
+    // the threshold replaces any basis point threshold
+    pub fn reset_members(&mut self, members: Vec<Pubkey>, weights: Vec<u16>, threshold: u16) -> Result<()>{
+        self.permissions = vec![Ms::PERMISSION_ALL; members.len()];
+        self.keys = members;
+        self.weights = weights;
+        self.threshold = threshold;
+        self.threshold_bps = None;
+        self.clamp_thresholds();
+        Ok(())
+    }
+
+    // replace the permission flags of a member
+    pub fn set_permissions(&mut self, member: Pubkey, permissions: u8) -> Result<()>{
+        if let Some(ind) = self.is_member(member) {
+            self.permissions[ind] = permissions;
+            self.clamp_thresholds();
+        }
+        Ok(())
+    }
+
+    // lower any threshold that can no longer be reached by the total voting weight
+    fn clamp_thresholds(&mut self) {
+        let total_weight: u16 = self.total_weight().try_into().unwrap_or(u16::MAX);
+        self.threshold = self.threshold.min(total_weight);
+        self.reject_threshold = self.reject_threshold.map(|t| t.min(total_weight));
+        self.cancel_threshold = self.cancel_threshold.map(|t| t.min(total_weight));
+        for authority_threshold in self.authority_thresholds.iter_mut() {
+            authority_threshold.threshold = authority_threshold.threshold.min(total_weight);
+        }
+    }
+
+    // the approval threshold for transactions using the given authority
+    pub fn threshold_for(&self, authority_index: u32) -> u16 {
+        self.authority_thresholds.iter()
+            .find(|t| t.authority_index == authority_index)
+            .map_or_else(|| self.current_threshold(), |t| t.threshold)
+    }
+
+    // the multisig threshold, computed from the total weight when set in basis points
+    pub fn current_threshold(&self) -> u16 {
+        match self.threshold_bps {
+            Some(bps) => {
+                // round up, so the threshold is never below the percentage
+                let max_bps = u64::from(Ms::MAX_THRESHOLD_BPS);
+                let threshold = (u64::from(self.total_weight()) * u64::from(bps)).div_ceil(max_bps);
+                threshold.max(1).try_into().unwrap_or(u16::MAX)
+            },
+            None => self.threshold
+        }
+    }
+
+    // the approval threshold for a transaction, the highest threshold among
+    // its own authority and every other authority signing its instructions
+    pub fn transaction_threshold(&self, transaction: &MsTransaction) -> u16 {
+        transaction.signing_authorities.iter()
+            .map(|&authority_index| self.threshold_for(authority_index))
+            .fold(self.threshold_for(transaction.authority_index), u16::max)
+    }
+
+    // check if the approvals of a transaction reach its threshold
+    pub fn is_approved(&self, transaction: &MsTransaction) -> bool {
+        self.tally(&transaction.approved) >= u32::from(self.transaction_threshold(transaction))
+    }
+
+    // the rejection weight needed to reject a transaction
+    // by default, this is the point where the approval threshold can no longer be reached
+    // ie total weight 7, threshold 3, reject threshold = 5
+    pub fn reject_threshold_for(&self, transaction: &MsTransaction) -> u32 {
+        match self.reject_threshold {
+            Some(reject_threshold) => u32::from(reject_threshold),
+            None => self.total_weight()
+                .saturating_sub(u32::from(self.transaction_threshold(transaction)))
+                .saturating_add(1)
+        }
+    }
+
+    // the cancellation weight needed to cancel a transaction
+    pub fn cancel_threshold_for(&self, transaction: &MsTransaction) -> u32 {
+        u32::from(self.cancel_threshold.unwrap_or_else(|| self.transaction_threshold(transaction)))
+    }
+
+    // set or remove (None) the threshold override for an authority
+    pub fn set_authority_threshold(&mut self, authority_index: u32, threshold: Option<u16>) -> Result<()>{
+        let existing = self.authority_thresholds.iter().position(|t| t.authority_index == authority_index);
+        match (existing, threshold) {
+            (Some(ind), Some(threshold)) => self.authority_thresholds[ind].threshold = threshold,
+            (Some(ind), None) => { self.authority_thresholds.remove(ind); },
+            (None, Some(threshold)) => self.authority_thresholds.push(MsAuthorityThreshold { authority_index, threshold }),
+            (None, None) => {}
+        };
+        Ok(())
+    }
+
     pub fn change_threshold(&mut self, threshold: u16) -> Result<()>{
         self.threshold = threshold;
         Ok(())
     }
 
+    // track a transaction that has become active
+    pub fn open_transaction(&mut self) -> Result<()>{
+        self.open_transactions = self.open_transactions.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    // stop tracking a transaction that left the active/execute ready states
+    pub fn settle_transaction(&mut self) -> Result<()>{
+        self.open_transactions = self.open_transactions.saturating_sub(1);
+        Ok(())
+    }
+
+    // track a newly created transaction account
+    pub fn record_transaction_account(&mut self) -> Result<()>{
+        self.transaction_accounts = self.transaction_accounts.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    // stop tracking a transaction account once it's closed
+    pub fn release_transaction_account(&mut self) -> Result<()>{
+        self.transaction_accounts = self.transaction_accounts.saturating_sub(1);
+        Ok(())
+    }
+
+    // track a newly created spending limit
+    pub fn record_spending_limit(&mut self) -> Result<()>{
+        self.spending_limits = self.spending_limits.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    // stop tracking a spending limit once it's removed
+    pub fn release_spending_limit(&mut self) -> Result<()>{
+        self.spending_limits = self.spending_limits.saturating_sub(1);
+        Ok(())
+    }
+
+}
+
+// threshold override for transactions using a specific authority
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone)]
+pub struct MsAuthorityThreshold {
+    pub authority_index: u32,
+    pub threshold: u16,
 }
 
+impl MsAuthorityThreshold {
+    pub const SIZE: usize = 4 + 2;
+}
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum MsTransactionStatus {
@@ -93,6 +434,16 @@ pub enum MsTransactionStatus {
     Executed,       // Transaction has been executed
     Rejected,       // Transaction has been rejected
     Cancelled,      // Transaction has been cancelled
+    Expired,        // Transaction passed its expiry before being executed
+    Stale,          // Transaction was deprecated by a multisig config change
+    Vetoed,         // Transaction has been vetoed by a guardian
+}
+
+// the point after which a transaction can no longer be voted on or executed
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq)]
+pub enum MsExpiry {
+    Timestamp(i64), // unix timestamp
+    Slot(u64),      // slot
 }
 
 
@@ -109,7 +460,10 @@ pub struct MsTransaction {
     pub approved: Vec<Pubkey>,          // keys that have approved/signed
     pub rejected: Vec<Pubkey>,          // keys that have rejected
     pub cancelled: Vec<Pubkey>,         // keys that have cancelled (ExecuteReady only)
-    pub executed_index: u8              // if Tx is executed sequentially, track latest
+    pub executed_index: u8,             // if Tx is executed sequentially, track latest
+    pub execute_ready_at: i64,          // timestamp of when the transaction became execute ready
+    pub expiry: Option<MsExpiry>,       // optional deadline for voting and execution
+    pub signing_authorities: Vec<u32>,  // other authorities that sign attached instructions
 }
 
 impl MsTransaction {
@@ -122,12 +476,24 @@ impl MsTransaction {
         (1 + 12) +                          // the enum size
         1 +                                 // the number of instructions (attached)
         1 +                                 // space for tx bump
-        1;                                  // track index if executed sequentially
+        1 +                                 // track index if executed sequentially
+        8 +                                 // execute ready timestamp
+        (1 + 1 + 8) +                       // the optional expiry
+        4;                                  // for signing authorities vec length
 
     pub fn initial_size_with_members(members_len: usize) -> usize {
         MsTransaction::MINIMUM_SIZE + (3 * (4 + (members_len * 32) ) )
     }
 
+    // the space needed for the current votes and signing authorities
+    pub fn size(&self) -> usize {
+        8 +                                 // Anchor discriminator
+        MsTransaction::MINIMUM_SIZE +
+        (3 * 4) +                           // the vote vec lengths
+        ((self.approved.len() + self.rejected.len() + self.cancelled.len()) * 32) +
+        (self.signing_authorities.len() * 4)
+    }
+
     pub fn init(&mut self, creator: Pubkey, multisig: Pubkey, transaction_index: u32, bump: u8, authority_index: u32, authority_bump: u8) -> Result<()>{
         self.creator = creator;
         self.ms = multisig;
@@ -141,6 +507,20 @@ impl MsTransaction {
         self.cancelled = Vec::new();
         self.bump = bump;
         self.executed_index = 0;
+        self.execute_ready_at = 0;
+        self.expiry = None;
+        self.signing_authorities = Vec::new();
+        Ok(())
+    }
+
+    // track an authority, other than the transaction's own, that signs an attached instruction
+    // custom authority instructions are co-signed by the transaction authority, so aren't tracked
+    pub fn add_signing_authority(&mut self, authority_index: Option<u32>, authority_type: &MsAuthorityType) -> Result<()>{
+        if let (Some(authority_index), MsAuthorityType::Default) = (authority_index, authority_type) {
+            if authority_index != self.authority_index && !self.signing_authorities.contains(&authority_index) {
+                self.signing_authorities.push(authority_index);
+            }
+        }
         Ok(())
     }
 
@@ -150,12 +530,29 @@ impl MsTransaction {
         Ok(())
     }
 
-    // change status to ExecuteReady
+    // drop votes from keys that are no longer members
+    pub fn discard_non_member_votes(&mut self, multisig: &Ms) -> Result<()>{
+        self.approved.retain(|&k| multisig.is_member(k).is_some());
+        self.rejected.retain(|&k| multisig.is_member(k).is_some());
+        self.cancelled.retain(|&k| multisig.is_member(k).is_some());
+        Ok(())
+    }
+
+    // change status to ExecuteReady, and start the time lock
     pub fn ready_to_execute(&mut self)-> Result<()>{
         self.status = MsTransactionStatus::ExecuteReady;
+        self.execute_ready_at = Clock::get()?.unix_timestamp;
         Ok(())
     }
 
+    // check if the time lock has passed since the transaction became execute ready
+    pub fn time_lock_elapsed(&self, time_lock_seconds: u32) -> bool {
+        match Clock::get() {
+            Ok(clock) => clock.unix_timestamp >= self.execute_ready_at.saturating_add(i64::from(time_lock_seconds)),
+            Err(_) => false
+        }
+    }
+
     // set status to Rejected
     pub fn set_rejected(&mut self) -> Result<()>{
         self.status = MsTransactionStatus::Rejected;
@@ -167,6 +564,42 @@ impl MsTransaction {
         Ok(())
     }
 
+    // set status to Vetoed
+    pub fn set_vetoed(&mut self) -> Result<()>{
+        self.status = MsTransactionStatus::Vetoed;
+        Ok(())
+    }
+
+    // set status to Stale
+    pub fn set_stale(&mut self) -> Result<()>{
+        self.status = MsTransactionStatus::Stale;
+        Ok(())
+    }
+
+    // set status to Expired
+    pub fn set_expired(&mut self) -> Result<()>{
+        self.status = MsTransactionStatus::Expired;
+        Ok(())
+    }
+
+    pub fn set_expiry(&mut self, expiry: Option<MsExpiry>) -> Result<()>{
+        self.expiry = expiry;
+        Ok(())
+    }
+
+    // check if the transaction has passed its expiry
+    pub fn is_expired(&self) -> bool {
+        let clock = match Clock::get() {
+            Ok(clock) => clock,
+            Err(_) => return false
+        };
+        match self.expiry {
+            Some(MsExpiry::Timestamp(timestamp)) => clock.unix_timestamp >= timestamp,
+            Some(MsExpiry::Slot(slot)) => clock.slot >= slot,
+            None => false
+        }
+    }
+
     // set status to executed
     pub fn set_executed(&mut self) -> Result<()>{
         self.status = MsTransactionStatus::Executed;
@@ -195,6 +628,22 @@ impl MsTransaction {
     }
 
 
+    // check if the transaction is finished or can no longer progress, and can be closed
+    pub fn is_closeable(&self, ms_change_index: u32) -> bool {
+        match self.status {
+            MsTransactionStatus::Executed |
+            MsTransactionStatus::Rejected |
+            MsTransactionStatus::Cancelled |
+            MsTransactionStatus::Expired |
+            MsTransactionStatus::Stale |
+            MsTransactionStatus::Vetoed => true,
+            // drafts and active transactions are deprecated by config changes
+            MsTransactionStatus::Draft |
+            MsTransactionStatus::Active => self.transaction_index <= ms_change_index,
+            MsTransactionStatus::ExecuteReady => false,
+        }
+    }
+
     // check if a user has voted already
     pub fn has_voted(&self, member: Pubkey) -> bool {
         let approved = self.approved.binary_search(&member).is_ok();
@@ -315,3 +764,12 @@ pub struct IncomingInstruction {
     pub keys: Vec<MsAccountMeta>,
     pub data: Vec<u8>
 }
+
+// an incoming instruction with its authority settings, as passed to add_instruction
+#[derive(AnchorSerialize,AnchorDeserialize, Clone)]
+pub struct IncomingInstructionArgs {
+    pub instruction: IncomingInstruction,
+    pub authority_index: Option<u32>,
+    pub authority_bump: Option<u8>,
+    pub authority_type: MsAuthorityType
+}
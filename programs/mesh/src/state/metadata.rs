@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+// descriptive information about a multisig, for front ends and tooling
+#[account]
+pub struct MultisigMetadata {
+    pub ms: Pubkey,                     // the multisig this describes, used to seed pda
+    pub name: String,                   // display name
+    pub description: String,            // longer description
+    pub uri: String,                    // image or external metadata uri
+    pub tags: Vec<MetadataTag>,         // arbitrary key/value pairs
+    pub bump: u8,                       // bump for the seed
+}
+
+// a key/value pair attached to the metadata
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MetadataTag {
+    pub key: String,
+    pub value: String,
+}
+
+impl MultisigMetadata {
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+    pub const MAX_URI_LEN: usize = 200;
+    pub const MAX_TAGS: usize = 16;
+    pub const MAX_TAG_KEY_LEN: usize = 32;
+    pub const MAX_TAG_VALUE_LEN: usize = 128;
+
+    // the space needed for the given contents
+    pub fn size_with(name: &str, description: &str, uri: &str, tags: &[MetadataTag]) -> usize {
+        8 +                             // Anchor discriminator
+        32 +                            // the multisig key
+        4 + name.len() +                // the name
+        4 + description.len() +         // the description
+        4 + uri.len() +                 // the uri
+        4 + tags.iter().map(|t| 4 + t.key.len() + 4 + t.value.len()).sum::<usize>() +    // the tags
+        1                               // bump
+    }
+
+    // check the contents are within the length limits
+    pub fn is_valid(name: &str, description: &str, uri: &str, tags: &[MetadataTag]) -> bool {
+        name.len() <= MultisigMetadata::MAX_NAME_LEN &&
+        description.len() <= MultisigMetadata::MAX_DESCRIPTION_LEN &&
+        uri.len() <= MultisigMetadata::MAX_URI_LEN &&
+        tags.len() <= MultisigMetadata::MAX_TAGS &&
+        tags.iter().all(|t| t.key.len() <= MultisigMetadata::MAX_TAG_KEY_LEN && t.value.len() <= MultisigMetadata::MAX_TAG_VALUE_LEN)
+    }
+
+    pub fn init(&mut self, multisig: Pubkey, name: String, description: String, uri: String, tags: Vec<MetadataTag>, bump: u8) -> Result<()> {
+        self.ms = multisig;
+        self.bump = bump;
+        self.update(name, description, uri, tags)
+    }
+
+    pub fn update(&mut self, name: String, description: String, uri: String, tags: Vec<MetadataTag>) -> Result<()> {
+        self.name = name;
+        self.description = description;
+        self.uri = uri;
+        self.tags = tags;
+        Ok(())
+    }
+
+    // the space needed for the current contents
+    pub fn size(&self) -> usize {
+        MultisigMetadata::size_with(&self.name, &self.description, &self.uri, &self.tags)
+    }
+}
@@ -1,2 +1,12 @@
 pub use mesh::*;
-pub mod mesh;
\ No newline at end of file
+pub mod mesh;
+pub use spending_limit::*;
+pub mod spending_limit;
+pub use delegation::*;
+pub mod delegation;
+pub use rotation::*;
+pub mod rotation;
+pub use recovery::*;
+pub mod recovery;
+pub use metadata::*;
+pub mod metadata;
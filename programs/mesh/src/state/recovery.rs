@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::state::mesh::Ms;
+
+// a pending recovery of a multisig, proposed and approved by its recovery keys
+#[account]
+pub struct Recovery {
+    pub ms: Pubkey,                     // the multisig being recovered
+    pub initiator: Pubkey,              // the recovery key that proposed it, receives the rent back
+    pub new_external_authority: Option<Pubkey>,     // the external authority to set, if any
+    pub new_members: Vec<Pubkey>,       // the member set to install, empty to keep the current members
+    pub new_weights: Vec<u16>,          // voting weights of the new members, same order as new_members
+    pub new_threshold: u16,             // the threshold to set along with the new members
+    pub approvals: Vec<Pubkey>,         // recovery keys that approved the recovery
+    pub approved_at: Option<i64>,       // when the recovery threshold was reached, starts the delay
+    pub bump: u8,                       // bump for the seed
+}
+
+impl Recovery {
+    pub fn size_with(members_len: usize, recovery_keys_len: usize) -> usize {
+        8 +                             // Anchor discriminator
+        32 +                            // the multisig key
+        32 +                            // the initiator key
+        (1 + 32) +                      // the optional new external authority
+        4 + (members_len * 32) +        // the new members
+        4 + (members_len * 2) +         // the new weights
+        2 +                             // the new threshold
+        4 + (recovery_keys_len * 32) +  // the approvals
+        (1 + 8) +                       // the optional approval timestamp
+        1                               // bump
+    }
+
+    // the space needed for the current contents
+    pub fn size(&self) -> usize {
+        Recovery::size_with(self.new_members.len(), self.approvals.len())
+    }
+
+    pub fn init(&mut self, multisig: Pubkey, initiator: Pubkey, new_external_authority: Option<Pubkey>, bump: u8) -> Result<()> {
+        self.ms = multisig;
+        self.initiator = initiator;
+        self.new_external_authority = new_external_authority;
+        self.new_members = Vec::new();
+        self.new_weights = Vec::new();
+        self.new_threshold = 0;
+        self.approvals = Vec::new();
+        self.approved_at = None;
+        self.bump = bump;
+        Ok(())
+    }
+
+    // set the member set to install, empty to keep the current members
+    pub fn set_new_members(&mut self, new_members: Vec<Pubkey>, new_weights: Vec<u16>, new_threshold: u16) -> Result<()> {
+        self.new_members = new_members;
+        self.new_weights = new_weights;
+        self.new_threshold = new_threshold;
+        Ok(())
+    }
+
+    // record the approval of a recovery key, keeping the approvals sorted
+    pub fn approve(&mut self, recovery_key: Pubkey) -> Result<()> {
+        if let Err(ind) = self.approvals.binary_search(&recovery_key) {
+            self.approvals.insert(ind, recovery_key);
+        }
+        Ok(())
+    }
+
+    // number of approvals from keys that are still recovery keys of the multisig
+    pub fn tally(&self, ms: &Ms) -> usize {
+        self.approvals.iter().filter(|&&k| ms.is_recovery_key(k).is_some()).count()
+    }
+
+    // check if the recovery delay has passed since the recovery was approved
+    pub fn delay_elapsed(&self, delay_seconds: u32) -> bool {
+        match (self.approved_at, Clock::get()) {
+            (Some(approved_at), Ok(clock)) => clock.unix_timestamp >= approved_at.saturating_add(i64::from(delay_seconds)),
+            _ => false
+        }
+    }
+}
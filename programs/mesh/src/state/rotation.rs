@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+// a member's request to move their seat to a new key, pending external authority approval
+#[account]
+pub struct MemberRotation {
+    pub ms: Pubkey,                     // the multisig this belongs to
+    pub old_member: Pubkey,             // the member key being replaced, used to seed pda
+    pub new_member: Pubkey,             // the key that takes over the seat
+    pub bump: u8,                       // bump for the seed
+}
+
+impl MemberRotation {
+    pub const SIZE: usize = 8 +         // Anchor discriminator
+        32 +                            // the multisig key
+        32 +                            // the old member key
+        32 +                            // the new member key
+        1;                              // bump
+
+    pub fn init(&mut self, multisig: Pubkey, old_member: Pubkey, new_member: Pubkey, bump: u8) -> Result<()> {
+        self.ms = multisig;
+        self.old_member = old_member;
+        self.new_member = new_member;
+        self.bump = bump;
+        Ok(())
+    }
+}
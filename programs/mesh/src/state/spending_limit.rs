@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+// how often the spending limit allowance is restored
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Eq)]
+pub enum SpendingLimitPeriod {
+    OneTime,    // never resets
+    Day,
+    Week,
+    Month,      // 30 days
+}
+
+impl SpendingLimitPeriod {
+    pub fn to_seconds(&self) -> Option<i64> {
+        match self {
+            SpendingLimitPeriod::OneTime => None,
+            SpendingLimitPeriod::Day => Some(24 * 60 * 60),
+            SpendingLimitPeriod::Week => Some(7 * 24 * 60 * 60),
+            SpendingLimitPeriod::Month => Some(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+// allowance for listed members to transfer from an authority (vault) without a vote
+#[account]
+pub struct SpendingLimit {
+    pub ms: Pubkey,                     // the multisig this belongs to
+    pub authority_index: u32,           // the authority (vault) funds are transferred from
+    pub mint: Pubkey,                   // the token mint, or Pubkey::default() for native SOL
+    pub amount: u64,                    // the allowance for each period
+    pub period: SpendingLimitPeriod,    // how often the allowance resets
+    pub remaining_amount: u64,          // the allowance left in the current period
+    pub last_reset: i64,                // the start of the current period
+    pub members: Vec<Pubkey>,           // the members allowed to use the spending limit
+    pub bump: u8,                       // bump for the seed
+}
+
+impl SpendingLimit {
+    pub fn size_with_members(members_len: usize) -> usize {
+        8 +                 // Anchor discriminator
+        32 +                // the multisig key
+        4 +                 // the authority index
+        32 +                // the mint
+        8 +                 // the amount
+        1 +                 // the period enum
+        8 +                 // the remaining amount
+        8 +                 // the last reset timestamp
+        4 +                 // for vec length
+        (members_len * 32) +
+        1                   // bump
+    }
+
+    pub fn init(&mut self, multisig: Pubkey, authority_index: u32, mint: Pubkey, members: Vec<Pubkey>, bump: u8) -> Result<()> {
+        self.ms = multisig;
+        self.authority_index = authority_index;
+        self.mint = mint;
+        self.members = members;
+        self.bump = bump;
+        Ok(())
+    }
+
+    // set the allowance for each period, starting a new period with the full amount
+    pub fn set_allowance(&mut self, amount: u64, period: SpendingLimitPeriod) -> Result<()> {
+        self.amount = amount;
+        self.period = period;
+        self.remaining_amount = amount;
+        self.last_reset = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    pub fn is_member(&self, member: Pubkey) -> bool {
+        self.members.binary_search(&member).is_ok()
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    // restore the allowance if one or more periods have passed since the last reset
+    pub fn reset_if_needed(&mut self, now: i64) -> Result<()> {
+        if let Some(period_seconds) = self.period.to_seconds() {
+            let periods_passed = now.saturating_sub(self.last_reset) / period_seconds;
+            if periods_passed > 0 {
+                self.remaining_amount = self.amount;
+                self.last_reset = self.last_reset.saturating_add(periods_passed.saturating_mul(period_seconds));
+            }
+        }
+        Ok(())
+    }
+
+    // deduct a transfer from the remaining allowance
+    pub fn spend(&mut self, amount: u64) -> Option<u64> {
+        self.remaining_amount = self.remaining_amount.checked_sub(amount)?;
+        Some(self.remaining_amount)
+    }
+}
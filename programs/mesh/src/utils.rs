@@ -0,0 +1,381 @@
+use std::convert::TryInto;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    instruction::Instruction,
+    program::{invoke, invoke_signed},
+    system_instruction::{allocate, assign, create_account, transfer},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::errors::*;
+use crate::state::mesh::*;
+
+// closes every instruction account attached to a transaction, sending the rent to sol_destination.
+// the instruction accounts must be provided in order, starting from instruction index 1
+pub fn close_instruction_accounts<'info>(
+    transaction: &Account<'info, MsTransaction>,
+    instruction_accounts: &[AccountInfo<'info>],
+    sol_destination: AccountInfo<'info>,
+) -> Result<()> {
+    if instruction_accounts.len() != usize::from(transaction.instruction_index) {
+        return err!(GraphsError::InvalidNumberOfAccounts);
+    }
+
+    for (i, ix_account) in (1..=transaction.instruction_index).zip(instruction_accounts.iter()) {
+        // deserializing checks the owner and account discriminator
+        let ms_ix: Account<MsInstruction> = Account::try_from(ix_account)?;
+
+        // check the instruction account matches the pda for this transaction and index
+        let ix_pda = Pubkey::create_program_address(&[
+            b"squad",
+            transaction.key().as_ref(),
+            &i.to_le_bytes(),
+            b"instruction",
+            &[ms_ix.bump]],
+            &crate::ID
+        ).map_err(|_| GraphsError::InvalidInstructionAccount)?;
+        if &ix_pda != ix_account.key {
+            return err!(GraphsError::InvalidInstructionAccount);
+        }
+
+        ms_ix.close(sol_destination.clone())?;
+    }
+    Ok(())
+}
+
+// sorts members and removes duplicates, keeping the weights paired
+pub fn sort_members(members: Vec<Pubkey>, weights: Vec<u16>) -> (Vec<Pubkey>, Vec<u16>) {
+    let mut weighted_members: Vec<(Pubkey, u16)> = members.into_iter().zip(weights).collect();
+    weighted_members.sort_by_key(|&(member, _)| member);
+    weighted_members.dedup_by_key(|&mut (member, _)| member);
+    weighted_members.into_iter().unzip()
+}
+
+// resolves the authority an attached instruction will be signed with
+// if no authority values are passed in, regardless of what the authority type is,
+// the authority specified in the transaction is used and the type is set to Default
+pub fn resolve_instruction_authority(
+    transaction: &MsTransaction,
+    authority_index: Option<u32>,
+    authority_bump: Option<u8>,
+    authority_type: MsAuthorityType,
+) -> Result<(Option<u32>, Option<u8>, MsAuthorityType)> {
+    // check the proper authority level option is set
+    if authority_type != MsAuthorityType::Default && authority_type != MsAuthorityType::Custom {
+        return err!(GraphsError::InvalidAuthorityType);
+    }
+
+    match (authority_index, authority_bump) {
+        (None, None) => Ok((Some(transaction.authority_index), Some(transaction.authority_bump), MsAuthorityType::Default)),
+        (Some(_), Some(_)) => Ok((authority_index, authority_bump, authority_type)),
+        // if one or the other is specified, throw an error
+        _ => err!(GraphsError::InvalidAuthorityIndex)
+    }
+}
+
+// instructions signed by the internal authority (index 0) may only call back into this program
+// default instructions are signed by their resolved authority, custom ones by the transaction authority
+pub fn check_internal_instruction(
+    transaction: &MsTransaction,
+    instruction: &IncomingInstruction,
+    authority_index: Option<u32>,
+    authority_type: &MsAuthorityType,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let signing_authority = match authority_type {
+        MsAuthorityType::Default => authority_index,
+        _ => Some(transaction.authority_index),
+    };
+    if signing_authority == Some(Ms::INTERNAL_AUTHORITY_INDEX) && instruction.program_id != *program_id {
+        return err!(GraphsError::InvalidInternalInstruction);
+    }
+    Ok(())
+}
+
+// discards votes from removed members and re-evaluates the approvals against the
+// current threshold, moving the transaction back to Active if they fall short.
+// returns whether the transaction is still execute ready
+pub fn retally_transaction(multisig: &Ms, transaction: &mut MsTransaction) -> Result<bool> {
+    transaction.discard_non_member_votes(multisig)?;
+    if transaction.status == MsTransactionStatus::ExecuteReady && !multisig.is_approved(transaction) {
+        transaction.activate()?;
+    }
+    Ok(transaction.status == MsTransactionStatus::ExecuteReady)
+}
+
+// grows a transaction account to fit its votes, as members added after its creation
+// can vote when it is re-tallied. the voter pays for the extra space
+pub fn grow_transaction<'info>(
+    transaction: &Account<'info, MsTransaction>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    grow_account(
+        &transaction.to_account_info(),
+        payer,
+        system_program,
+        &Rent::get()?,
+        transaction.size(),
+    )
+}
+
+// records an approval for the member, replacing a previous rejection, and marks
+// the transaction as execute ready once the approvals reach the threshold
+pub fn cast_approval(multisig: &Ms, transaction: &mut MsTransaction, member: Pubkey) -> Result<()> {
+    retally_transaction(multisig, transaction)?;
+
+    // if they have previously voted to reject, remove that item (change vote check)
+    if let Some(ind) = transaction.has_voted_reject(member) { transaction.remove_reject(ind)?; }
+
+    // if they haven't already approved
+    if transaction.has_voted_approve(member).is_none() { transaction.sign(member)?; }
+
+    // if the weight of the signers reaches threshold, mark the transaction as execute ready
+    if multisig.is_approved(transaction) {
+        transaction.ready_to_execute()?;
+    }
+    Ok(())
+}
+
+// records a rejection for the member, replacing a previous approval, and marks
+// the transaction as rejected once the rejections reach the reject threshold
+pub fn cast_rejection(multisig: &mut Ms, transaction: &mut MsTransaction, member: Pubkey) -> Result<()> {
+    retally_transaction(multisig, transaction)?;
+
+    // if they have previously voted to approve, remove that item (change vote check)
+    if let Some(ind) = transaction.has_voted_approve(member) { transaction.remove_approve(ind)?; }
+
+    // check if they haven't already voted reject
+    if transaction.has_voted_reject(member).is_none() { transaction.reject(member)?; }
+
+    // if the weight of the rejections reaches the reject threshold, mark the transaction as rejected
+    let reject_threshold = multisig.reject_threshold_for(transaction);
+    if multisig.tally(&transaction.rejected) >= reject_threshold {
+        transaction.set_rejected()?;
+        multisig.settle_transaction()?;
+    }
+    Ok(())
+}
+
+// grows an account owned by this program to new_len if it's currently smaller,
+// and tops up the rent exemption from the payer
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    new_len: usize,
+) -> Result<()> {
+    if *account.owner != crate::ID {
+        return err!(GraphsError::InvalidInstructionAccount);
+    }
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+
+    // reallocate more space
+    AccountInfo::realloc(account, new_len, false)?;
+    // if more lamports are needed, transfer them to the account
+    let rent_exempt_lamports = rent.minimum_balance(new_len).max(1);
+    let top_up_lamports = rent_exempt_lamports.saturating_sub(account.lamports());
+    if top_up_lamports > 0 {
+        invoke(
+            &transfer(payer.key, account.key, top_up_lamports),
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+// creates a pda owned by this program the same way anchor's init does - if someone
+// already sent lamports to the address, create_account would fail, so top up the
+// shortfall and allocate/assign it instead
+pub fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        invoke_signed(
+            &create_account(
+                payer.key,
+                account.key,
+                rent.minimum_balance(space),
+                space.try_into().unwrap(),
+                &crate::ID,
+            ),
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program.clone(),
+            ],
+            &[seeds],
+        )?;
+        return Ok(());
+    }
+
+    let top_up_lamports = rent.minimum_balance(space).max(1).saturating_sub(current_lamports);
+    if top_up_lamports > 0 {
+        invoke(
+            &transfer(payer.key, account.key, top_up_lamports),
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+    invoke_signed(
+        &allocate(account.key, space.try_into().unwrap()),
+        &[
+            account.clone(),
+            system_program.clone(),
+        ],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &assign(account.key, &crate::ID),
+        &[
+            account.clone(),
+            system_program.clone(),
+        ],
+        &[seeds],
+    )?;
+    Ok(())
+}
+
+// shrinks an account owned by this program down to new_len if it's currently larger,
+// and sends the lamports no longer needed for rent exemption to the receiver
+pub fn shrink_account<'info>(
+    account: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if *account.owner != crate::ID {
+        return err!(GraphsError::InvalidInstructionAccount);
+    }
+    if account.data_len() <= new_len {
+        return Ok(());
+    }
+
+    AccountInfo::realloc(account, new_len, false)?;
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(new_len).max(1);
+    let surplus_lamports = account.lamports().saturating_sub(rent_exempt_lamports);
+    if surplus_lamports > 0 {
+        **account.try_borrow_mut_lamports()? -= surplus_lamports;
+        **receiver.try_borrow_mut_lamports()? += surplus_lamports;
+    }
+    Ok(())
+}
+
+// the spl token program
+pub mod token_program {
+    use anchor_lang::prelude::*;
+    declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+
+// reads the mint and owner of an spl token account
+pub fn read_token_account(token_account: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+    if *token_account.owner != token_program::ID {
+        return err!(GraphsError::InvalidInstructionAccount);
+    }
+    let data = token_account.try_borrow_data()?;
+    // the mint and owner are the first two fields of the token account layout
+    if data.len() < 64 {
+        return err!(GraphsError::InvalidInstructionAccount);
+    }
+    let mint = Pubkey::new_from_array(data[0..32].try_into().unwrap());
+    let owner = Pubkey::new_from_array(data[32..64].try_into().unwrap());
+    Ok((mint, owner))
+}
+
+// builds an spl token transfer instruction
+pub fn token_transfer_instruction(source: &Pubkey, destination: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    // the transfer instruction tag followed by the amount
+    let mut data = vec![3u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: token_program::ID,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+// the vote byte of an approval in the signed message
+pub const VOTE_APPROVE: u8 = 1;
+
+// the canonical message members sign off-chain to approve a transaction:
+// multisig (32) + transaction (32) + transaction index (4, le) + vote (1)
+pub fn approval_message(multisig: &Pubkey, transaction: &Pubkey, transaction_index: u32) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 32 + 4 + 1);
+    message.extend_from_slice(multisig.as_ref());
+    message.extend_from_slice(transaction.as_ref());
+    message.extend_from_slice(&transaction_index.to_le_bytes());
+    message.push(VOTE_APPROVE);
+    message
+}
+
+// the size of each signature offsets entry in an ed25519 program instruction
+const ED25519_OFFSETS_SIZE: usize = 14;
+
+// collects the keys whose signature over the message was verified by the ed25519 program
+// instructions preceding the current instruction. the signature, key and message must be
+// contained in the ed25519 instruction itself
+pub fn ed25519_signers(instructions: &AccountInfo, message: &[u8]) -> Result<Vec<Pubkey>> {
+    let current_index = load_current_index_checked(instructions)?;
+    let mut signers: Vec<Pubkey> = Vec::new();
+
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(usize::from(index), instructions)?;
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+
+        let data = &ix.data;
+        let num_signatures = usize::from(*data.first().ok_or(GraphsError::InvalidSignatures)?);
+        for i in 0..num_signatures {
+            // offsets start after the signature count and a padding byte
+            let start = 2 + (i * ED25519_OFFSETS_SIZE);
+            let offsets = data.get(start..start + ED25519_OFFSETS_SIZE).ok_or(GraphsError::InvalidSignatures)?;
+            let read_u16 = |at: usize| usize::from(u16::from_le_bytes([offsets[at], offsets[at + 1]]));
+
+            // u16::MAX refers to the ed25519 instruction itself
+            let signature_ix_index = read_u16(2);
+            let public_key_offset = read_u16(4);
+            let public_key_ix_index = read_u16(6);
+            let message_offset = read_u16(8);
+            let message_size = read_u16(10);
+            let message_ix_index = read_u16(12);
+            let own_ix = usize::from(u16::MAX);
+            if signature_ix_index != own_ix || public_key_ix_index != own_ix || message_ix_index != own_ix {
+                return err!(GraphsError::InvalidSignatures);
+            }
+
+            let public_key = data.get(public_key_offset..public_key_offset + 32).ok_or(GraphsError::InvalidSignatures)?;
+            let signed_message = data.get(message_offset..message_offset + message_size).ok_or(GraphsError::InvalidSignatures)?;
+            if signed_message != message {
+                continue;
+            }
+
+            let signer = Pubkey::new_from_array(public_key.try_into().unwrap());
+            if let Err(ind) = signers.binary_search(&signer) {
+                signers.insert(ind, signer);
+            }
+        }
+    }
+    Ok(signers)
+}
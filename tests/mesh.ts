@@ -2,7 +2,7 @@ import { expect } from "chai";
 import fs from "fs";
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { createAssociatedTokenAccountInstruction, createInitializeMintInstruction, createMintToInstruction } from "@solana/spl-token";
+import { createAssociatedTokenAccountInstruction, createInitializeMintInstruction, createMint, createMintToInstruction, getAccount, getOrCreateAssociatedTokenAccount, mintTo } from "@solana/spl-token";
 import { Mesh } from "../idl/mesh";
 
 import { execSync } from "child_process";
@@ -36,13 +36,13 @@ describe("Programs", function(){
       anchor.web3.Keypair.generate(),
       anchor.web3.Keypair.generate(),
     ];
-    const createKey = anchor.web3.Keypair.generate().publicKey;
+    const createKey = anchor.web3.Keypair.generate();
 
     this.beforeAll(async function(){
       deployMesh();
       console.log("✔ Mesh Program deployed.");
       meshProgram = anchor.workspace.Mesh as Program<Mesh>;
-      [ms] = await getMsPDA(createKey, meshProgram.programId);
+      [ms] = await getMsPDA(createKey.publicKey, meshProgram.programId);
       await provider.connection.requestAirdrop(members[0].publicKey, anchor.web3.LAMPORTS_PER_SOL * 2);
       await provider.connection.requestAirdrop(members[1].publicKey, anchor.web3.LAMPORTS_PER_SOL * 2);
       await provider.connection.requestAirdrop(members[2].publicKey, anchor.web3.LAMPORTS_PER_SOL * 2);
@@ -55,11 +55,14 @@ describe("Programs", function(){
             members[2].publicKey
         ];
         try {
-            await meshProgram.methods.create(provider.wallet.publicKey, 1, createKey, initMembers)
+            const initWeights = initMembers.map(() => 1);
+            await meshProgram.methods.create(provider.wallet.publicKey, 1, initMembers, initWeights)
                 .accounts({
                     multisig: ms,
+                    createKey: createKey.publicKey,
                     creator: provider.wallet.publicKey
                 })
+                .signers([createKey])
                 .rpc();
         }catch(e) {
             console.log(e);
@@ -76,7 +79,7 @@ describe("Programs", function(){
         // find a key to remove
         const removeKey = (msState.keys as anchor.web3.PublicKey[]).shift();
         try {
-            await meshProgram.methods.removeMember(removeKey)
+            await meshProgram.methods.removeMember(removeKey, false)
                 .accounts({
                     multisig: ms,
                 })
@@ -99,7 +102,7 @@ describe("Programs", function(){
         });
         const signer = members[signerIndex];
 
-        const removeIx = await meshProgram.methods.removeMember(removeKey)
+        const removeIx = await meshProgram.methods.removeMember(removeKey, false)
             .accounts({
                 multisig: ms,
             })
@@ -138,7 +141,7 @@ describe("Programs", function(){
 
         // create the tx with authority 1
         try {
-            await meshProgram.methods.createTransaction(1)
+            await meshProgram.methods.createTransaction(1, null)
                 .accounts({
                     multisig: ms,
                     transaction: tx,
@@ -168,7 +171,7 @@ describe("Programs", function(){
 
         // activate and approve
         try {
-            await meshProgram.methods.activateTransaction()
+            await meshProgram.methods.activateTransaction(null)
                 .accounts({
                     multisig: ms,
                     transaction: tx,
@@ -241,7 +244,7 @@ describe("Programs", function(){
         
         // create the tx with authority 1
         try {
-            await meshProgram.methods.createTransaction(1)
+            await meshProgram.methods.createTransaction(1, null)
                 .accounts({
                     multisig: ms,
                     transaction: tx,
@@ -289,7 +292,7 @@ describe("Programs", function(){
 
         // activate and approve
         try {
-            await meshProgram.methods.activateTransaction()
+            await meshProgram.methods.activateTransaction(null)
                 .accounts({
                     multisig: ms,
                     transaction: tx,
@@ -366,7 +369,7 @@ describe("Programs", function(){
 
         // create the tx with authority 1 as default
         try {
-            await meshProgram.methods.createTransaction(1)
+            await meshProgram.methods.createTransaction(1, null)
                 .accounts({
                     multisig: ms,
                     transaction: tx,
@@ -414,7 +417,7 @@ describe("Programs", function(){
 
         // activate and approve
         try {
-            await meshProgram.methods.activateTransaction()
+            await meshProgram.methods.activateTransaction(null)
                 .accounts({
                     multisig: ms,
                     transaction: tx,
@@ -482,7 +485,7 @@ describe("Programs", function(){
 
       // create the tx with authority 1 as default
       try {
-        await meshProgram.methods.createTransaction(1)
+        await meshProgram.methods.createTransaction(1, null)
             .accounts({
                 multisig: ms,
                 transaction: tx,
@@ -628,7 +631,7 @@ describe("Programs", function(){
 
         // activate and approve
         try {
-          await meshProgram.methods.activateTransaction()
+          await meshProgram.methods.activateTransaction(null)
             .accounts({
               multisig: ms,
               transaction: tx,
@@ -673,4 +676,2172 @@ describe("Programs", function(){
     });
   });
 
+  // test suite for multisig governance: setup, authority changes and recovery
+  describe("Mesh Program governance", function(){
+    let meshProgram;
+    let members = [
+      anchor.web3.Keypair.generate(),
+      anchor.web3.Keypair.generate(),
+      anchor.web3.Keypair.generate(),
+    ];
+
+    this.beforeAll(async function(){
+      deployMesh();
+      meshProgram = anchor.workspace.Mesh as Program<Mesh>;
+      for (const member of members) {
+        const ad = await provider.connection.requestAirdrop(member.publicKey, anchor.web3.LAMPORTS_PER_SOL * 2);
+        await provider.connection.confirmTransaction(ad);
+      }
+    });
+
+    // creates a multisig of all members with weight 1, returns its address
+    const createMultisig = async (externalAuthority: anchor.web3.PublicKey | null, threshold: number, createKey = anchor.web3.Keypair.generate()) => {
+      const [ms] = await getMsPDA(createKey.publicKey, meshProgram.programId);
+      await meshProgram.methods.create(externalAuthority, threshold, members.map((m) => m.publicKey), members.map(() => 1))
+        .accounts({
+          multisig: ms,
+          createKey: createKey.publicKey,
+          creator: provider.wallet.publicKey
+        })
+        .signers([createKey])
+        .rpc();
+      return ms;
+    };
+
+    // creates and activates a transaction with the given instructions, returns its address
+    const proposeTransaction = async (ms: anchor.web3.PublicKey, authorityIndex: number, instructions: {ix: anchor.web3.TransactionInstruction, authorityIndex: number | null, authorityBump: number | null}[], expiry = null) => {
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const [tx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+      await meshProgram.methods.createTransaction(authorityIndex, expiry)
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      for (const [i, instruction] of instructions.entries()) {
+        const [ix] = await getIxPDA(tx, new anchor.BN(i + 1), meshProgram.programId);
+        await meshProgram.methods.addInstruction(instruction.ix, instruction.authorityIndex, instruction.authorityBump, {default:{}})
+          .accounts({
+            multisig: ms,
+            transaction: tx,
+            instruction: ix,
+            creator: members[0].publicKey
+          })
+          .signers([members[0]])
+          .rpc();
+      }
+      await meshProgram.methods.activateTransaction(null)
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      return tx;
+    };
+
+    const approve = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, member: anchor.web3.Keypair) => {
+      await meshProgram.methods.approveTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          member: member.publicKey
+        })
+        .signers([member])
+        .rpc();
+    };
+
+    it("Create a multisig with an external authority", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      expect(msState.threshold).to.equal(2);
+      expect((msState.keys as anchor.web3.PublicKey[]).length).to.equal(3);
+    });
+
+    it("Create a self-governed multisig", async function(){
+      const ms = await createMultisig(null, 2);
+
+      // the external authority is the multisig's internal authority (index 0)
+      const [internalAuthority] = await getAuthorityPDA(ms, new anchor.BN(0), meshProgram.programId);
+      let msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(internalAuthority.toBase58());
+
+      // config changes go through a proposal signed by the internal authority. it pays for
+      // any reallocation, so give it some lamports
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: internalAuthority,
+        lamports: LAMPORTS_PER_SOL / 10
+      })));
+      const newMember = anchor.web3.Keypair.generate();
+      const addMemberIx = await meshProgram.methods.addMember(newMember.publicKey, 1, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: internalAuthority
+        })
+        .instruction();
+      const tx = await proposeTransaction(ms, 0, [{ix: addMemberIx, authorityIndex: null, authorityBump: null}]);
+      await approve(ms, tx, members[0]);
+      await approve(ms, tx, members[1]);
+      await executeTransaction(tx, members[0] as unknown as anchor.Wallet, provider, meshProgram, members[0].publicKey, [members[0]]);
+
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executed");
+      msState = await meshProgram.account.ms.fetch(ms);
+      expect((msState.keys as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.include(newMember.publicKey.toBase58());
+      expect(msState.msChangeIndex).to.equal(txState.transactionIndex);
+    });
+
+    it("A closed multisig can only be re-created by its create key", async function(){
+      const createKey = anchor.web3.Keypair.generate();
+      const ms = await createMultisig(provider.wallet.publicKey, 1, createKey);
+      await meshProgram.methods.closeMultisig()
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey,
+          receiver: provider.wallet.publicKey
+        })
+        .rpc();
+      expect(await provider.connection.getAccountInfo(ms)).to.be.null;
+
+      // without the create key signature the address can't be taken over
+      const createIx = await meshProgram.methods.create(provider.wallet.publicKey, 1, [provider.wallet.publicKey], [1])
+        .accounts({
+          multisig: ms,
+          createKey: createKey.publicKey,
+          creator: provider.wallet.publicKey
+        })
+        .instruction();
+      createIx.keys.forEach((k) => {
+        if (k.pubkey.equals(createKey.publicKey)) {
+          k.isSigner = false;
+        }
+      });
+      let failed = false;
+      try {
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(createIx));
+      }catch(e){
+        failed = true;
+      }
+      expect(failed).to.be.true;
+      expect(await provider.connection.getAccountInfo(ms)).to.be.null;
+
+      // the create key holder can re-create it
+      await createMultisig(provider.wallet.publicKey, 1, createKey);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.createKey.toBase58()).to.equal(createKey.publicKey.toBase58());
+    });
+
+    const getRecoveryPDA = (ms: anchor.web3.PublicKey) => anchor.web3.PublicKey.findProgramAddressSync([
+      Buffer.from("squad"),
+      ms.toBuffer(),
+      Buffer.from("recovery")
+    ], meshProgram.programId);
+
+    const setRecovery = async (ms: anchor.web3.PublicKey, recoveryKeys: anchor.web3.PublicKey[], threshold: number, delay: number) => {
+      await meshProgram.methods.setRecovery(recoveryKeys, threshold, delay)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    const approveRecovery = async (ms: anchor.web3.PublicKey, recoveryKey: anchor.web3.Keypair) => {
+      const [recovery] = getRecoveryPDA(ms);
+      await meshProgram.methods.approveRecovery()
+        .accounts({
+          multisig: ms,
+          recovery,
+          recoveryKey: recoveryKey.publicKey
+        })
+        .signers([recoveryKey])
+        .rpc();
+    };
+
+    const finalizeRecovery = async (ms: anchor.web3.PublicKey, initiator: anchor.web3.PublicKey) => {
+      const [recovery] = getRecoveryPDA(ms);
+      await meshProgram.methods.finalizeRecovery()
+        .accounts({
+          multisig: ms,
+          recovery,
+          payer: provider.wallet.publicKey,
+          initiator
+        })
+        .rpc();
+    };
+
+    it("Recover a multisig with recovery keys, including keys added mid-recovery", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const newAuthority = anchor.web3.Keypair.generate().publicKey;
+      // members double as recovery keys since they are funded
+      await setRecovery(ms, [members[0].publicKey, members[1].publicKey], 2, 0);
+
+      const [recovery] = getRecoveryPDA(ms);
+      await meshProgram.methods.initiateRecovery(newAuthority, [], [], 0)
+        .accounts({
+          multisig: ms,
+          recovery,
+          recoveryKey: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+
+      // a recovery key added after initiation can still approve
+      await setRecovery(ms, members.map((m) => m.publicKey), 3, 0);
+      await approveRecovery(ms, members[1]);
+      await approveRecovery(ms, members[2]);
+      const recoveryState = await meshProgram.account.recovery.fetch(recovery);
+      expect((recoveryState.approvals as anchor.web3.PublicKey[]).length).to.equal(3);
+
+      await finalizeRecovery(ms, members[0].publicKey);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(newAuthority.toBase58());
+      expect(await provider.connection.getAccountInfo(recovery)).to.be.null;
+    });
+
+    it("An approved recovery can't be finalized once recovery is disabled", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      await setRecovery(ms, [members[0].publicKey], 1, 0);
+
+      const [recovery] = getRecoveryPDA(ms);
+      await meshProgram.methods.initiateRecovery(anchor.web3.Keypair.generate().publicKey, [], [], 0)
+        .accounts({
+          multisig: ms,
+          recovery,
+          recoveryKey: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+
+      await setRecovery(ms, [], 0, 0);
+      let failed = false;
+      try {
+        await finalizeRecovery(ms, members[0].publicKey);
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal("InvalidRecovery");
+      }
+      expect(failed).to.be.true;
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+    });
+
+    it("Config changes deprecate in-flight transactions unless re-tallied", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const newMember = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(newMember.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+
+      const deprecatedTx = await proposeTransaction(ms, 1, []);
+      const retalliedTx = await proposeTransaction(ms, 1, []);
+      await approve(ms, retalliedTx, members[0]);
+      await approve(ms, retalliedTx, members[1]);
+
+      // removing an approver with re-tally keeps both transactions alive
+      await meshProgram.methods.removeMember(members[1].publicKey, true)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      // on execute, the removed member's approval no longer counts and it goes back to voting
+      await meshProgram.methods.executeTransaction(Buffer.from([]))
+        .accounts({
+          multisig: ms,
+          transaction: retalliedTx,
+          member: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      let txState = await meshProgram.account.msTransaction.fetch(retalliedTx);
+      expect(txState.status).to.haveOwnProperty("active");
+      expect((txState.approved as anchor.web3.PublicKey[]).length).to.equal(1);
+
+      // a member added with re-tally can vote on it
+      await meshProgram.methods.addMember(newMember.publicKey, 1, true)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      await approve(ms, retalliedTx, newMember);
+      txState = await meshProgram.account.msTransaction.fetch(retalliedTx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      const approved = (txState.approved as anchor.web3.PublicKey[]).map((k) => k.toBase58());
+      expect(approved).to.include(newMember.publicKey.toBase58());
+      expect(approved).to.not.include(members[1].publicKey.toBase58());
+
+      // a change without re-tally deprecates everything in flight
+      await meshProgram.methods.changeThreshold(2, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      let failed = false;
+      try {
+        await approve(ms, deprecatedTx, members[0]);
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal("DeprecatedTransaction");
+      }
+      expect(failed).to.be.true;
+    });
+
+    it("A transaction grows to fit votes from members added with re-tally", async function(){
+      const createKey = anchor.web3.Keypair.generate();
+      const [ms] = await getMsPDA(createKey.publicKey, meshProgram.programId);
+      await meshProgram.methods.create(provider.wallet.publicKey, 1, [members[0].publicKey], [1])
+        .accounts({
+          multisig: ms,
+          createKey: createKey.publicKey,
+          creator: provider.wallet.publicKey
+        })
+        .signers([createKey])
+        .rpc();
+      const newMember = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(newMember.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+
+      // sized for a single member, so room for three votes
+      const tx = await proposeTransaction(ms, 1, []);
+      for (const key of [members[1].publicKey, members[2].publicKey, newMember.publicKey]) {
+        await meshProgram.methods.addMember(key, 1, true)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      }
+      await meshProgram.methods.changeThreshold(4, true)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      const sizeBefore = (await provider.connection.getAccountInfo(tx)).data.length;
+      for (const member of [members[0], members[1], members[2], newMember]) {
+        await approve(ms, tx, member);
+      }
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      const approved = (txState.approved as anchor.web3.PublicKey[]).map((k) => k.toBase58());
+      expect(approved).to.include(newMember.publicKey.toBase58());
+
+      // MsTransaction::size(): discriminator, MINIMUM_SIZE, the vote vec lengths and the votes
+      const minimumSize = 32 + 32 + 4 + 4 + 1 + (1 + 12) + 1 + 1 + 1 + 8 + (1 + 1 + 8) + 4;
+      const expectedSize = 8 + minimumSize + (3 * 4) +
+        ((txState.approved as anchor.web3.PublicKey[]).length +
+          (txState.rejected as anchor.web3.PublicKey[]).length +
+          (txState.cancelled as anchor.web3.PublicKey[]).length) * 32 +
+        (txState.signingAuthorities as number[]).length * 4;
+      const sizeAfter = (await provider.connection.getAccountInfo(tx)).data.length;
+      expect(sizeAfter).to.be.greaterThan(sizeBefore);
+      expect(sizeAfter).to.equal(expectedSize);
+    });
+
+    it("Instructions signed by the internal authority can only call the mesh program", async function(){
+      const ms = await createMultisig(null, 1);
+      const [internalAuthority, internalAuthorityBump] = await getAuthorityPDA(ms, new anchor.BN(0), meshProgram.programId);
+      const withdrawIx = await createTestTransferTransaction(internalAuthority, provider.wallet.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+
+      // signing with authority 0 from a vault 1 transaction is still an internal instruction
+      let failed = false;
+      try {
+        await proposeTransaction(ms, 1, [{ix: withdrawIx, authorityIndex: 0, authorityBump: internalAuthorityBump}]);
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal("InvalidInternalInstruction");
+      }
+      expect(failed).to.be.true;
+    });
+
+    it("Instructions signed by another vault need that vault's threshold", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      // vault 2 needs 3 approvals, the transaction's own vault 1 needs 1
+      await meshProgram.methods.setAuthorityThreshold(2, 3, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      const [vault2, vault2Bump] = await getAuthorityPDA(ms, new anchor.BN(2), meshProgram.programId);
+      const withdrawIx = await createTestTransferTransaction(vault2, provider.wallet.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      const tx = await proposeTransaction(ms, 1, [{ix: withdrawIx, authorityIndex: 2, authorityBump: vault2Bump}]);
+
+      await approve(ms, tx, members[0]);
+      let txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("active");
+
+      await approve(ms, tx, members[1]);
+      await approve(ms, tx, members[2]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+    });
+
+    // the message members sign off-chain to approve a transaction
+    const approvalMessage = (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, transactionIndex: number) => {
+      const index = Buffer.alloc(4);
+      index.writeUInt32LE(transactionIndex);
+      return Buffer.concat([ms.toBuffer(), tx.toBuffer(), index, Buffer.from([1])]);
+    };
+
+    const signApproval = (signer: anchor.web3.Keypair, message: Buffer) => anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message
+    });
+
+    const approveWithSignatures = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, signatureIxs: anchor.web3.TransactionInstruction[]) => {
+      await meshProgram.methods.approveWithSignatures()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          relayer: provider.wallet.publicKey
+        })
+        .preInstructions(signatureIxs)
+        .rpc();
+    };
+
+    it("Approve a transaction with a batch of member signatures", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const tx = await proposeTransaction(ms, 1, []);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      const message = approvalMessage(ms, tx, txState.transactionIndex);
+
+      await approveWithSignatures(ms, tx, [signApproval(members[0], message), signApproval(members[1], message)]);
+      const approvedState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(approvedState.status).to.haveOwnProperty("executeReady");
+      expect((approvedState.approved as anchor.web3.PublicKey[]).length).to.equal(2);
+    });
+
+    it("Signatures whose offsets point at another instruction are rejected", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const tx = await proposeTransaction(ms, 1, []);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      const signatureIx = signApproval(members[0], approvalMessage(ms, tx, txState.transactionIndex));
+      // point the signature, key and message at instruction 0 - still this instruction, so the
+      // ed25519 program accepts it, but the data could come from anywhere in the transaction
+      signatureIx.data.writeUInt16LE(0, 4);
+      signatureIx.data.writeUInt16LE(0, 8);
+      signatureIx.data.writeUInt16LE(0, 14);
+
+      let failed = false;
+      try {
+        await approveWithSignatures(ms, tx, [signatureIx]);
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal("InvalidSignatures");
+      }
+      expect(failed).to.be.true;
+    });
+
+    it("Signatures over another transaction's message are ignored", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const otherTx = await proposeTransaction(ms, 1, []);
+      const tx = await proposeTransaction(ms, 1, []);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      const message = approvalMessage(ms, tx, txState.transactionIndex);
+
+      // only the first signature is over this transaction's message
+      await approveWithSignatures(ms, tx, [
+        signApproval(members[0], message),
+        signApproval(members[1], approvalMessage(ms, otherTx, txState.transactionIndex)),
+        signApproval(members[2], approvalMessage(ms, tx, txState.transactionIndex + 1)),
+      ]);
+      const approvedState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(approvedState.status).to.haveOwnProperty("active");
+      expect((approvedState.approved as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal([members[0].publicKey.toBase58()]);
+
+      // with no signature over the message, there's nothing to approve
+      let failed = false;
+      try {
+        await approveWithSignatures(ms, otherTx, [signApproval(members[0], message)]);
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal("InvalidSignatures");
+      }
+      expect(failed).to.be.true;
+    });
+
+    it("A non-member signature fails the whole batch", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const tx = await proposeTransaction(ms, 1, []);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      const message = approvalMessage(ms, tx, txState.transactionIndex);
+
+      let failed = false;
+      try {
+        await approveWithSignatures(ms, tx, [signApproval(members[0], message), signApproval(anchor.web3.Keypair.generate(), message)]);
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal("MissingPermission");
+      }
+      expect(failed).to.be.true;
+      const unchangedState = await meshProgram.account.msTransaction.fetch(tx);
+      expect((unchangedState.approved as anchor.web3.PublicKey[]).length).to.equal(0);
+    });
+
+    it("A relayed signature can't turn a later on-chain rejection back into an approval", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const tx = await proposeTransaction(ms, 1, []);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      const signatureIx = signApproval(members[0], approvalMessage(ms, tx, txState.transactionIndex));
+      await approveWithSignatures(ms, tx, [signatureIx]);
+
+      // the member changes their mind on-chain
+      await meshProgram.methods.rejectTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          member: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+
+      // replaying the old signature leaves the rejection in place
+      await approveWithSignatures(ms, tx, [signatureIx]);
+      const replayedState = await meshProgram.account.msTransaction.fetch(tx);
+      expect((replayedState.approved as anchor.web3.PublicKey[]).length).to.equal(0);
+      expect((replayedState.rejected as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal([members[0].publicKey.toBase58()]);
+    });
+
+    const getSpendingLimitPDA = (ms: anchor.web3.PublicKey, authorityIndex: number, mint: anchor.web3.PublicKey) => {
+      const index = Buffer.alloc(4);
+      index.writeUInt32LE(authorityIndex);
+      return anchor.web3.PublicKey.findProgramAddressSync([
+        Buffer.from("squad"),
+        ms.toBuffer(),
+        index,
+        mint.toBuffer(),
+        Buffer.from("spending_limit")
+      ], meshProgram.programId);
+    };
+
+    // creates a spending limit on vault 1, returns its address
+    const createSpendingLimit = async (ms: anchor.web3.PublicKey, mint: anchor.web3.PublicKey, amount: number, period, listedMembers: anchor.web3.PublicKey[]) => {
+      const [spendingLimit] = getSpendingLimitPDA(ms, 1, mint);
+      await meshProgram.methods.createSpendingLimit(1, mint, new anchor.BN(amount), period, listedMembers)
+        .accounts({
+          multisig: ms,
+          spendingLimit,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      return spendingLimit;
+    };
+
+    const useSpendingLimit = async (ms: anchor.web3.PublicKey, spendingLimit: anchor.web3.PublicKey, source: anchor.web3.PublicKey, destination: anchor.web3.PublicKey, member: anchor.web3.Keypair, amount: number) => {
+      const [vault] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await meshProgram.methods.useSpendingLimit(new anchor.BN(amount))
+        .accounts({
+          multisig: ms,
+          spendingLimit,
+          vault,
+          source,
+          destination,
+          member: member.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID
+        })
+        .signers([member])
+        .rpc();
+    };
+
+    const expectError = async (code: string, fn: () => Promise<void>) => {
+      let failed = false;
+      try {
+        await fn();
+      }catch(e){
+        failed = true;
+        expect(e.error.errorCode.code).to.equal(code);
+      }
+      expect(failed).to.be.true;
+    };
+
+    it("Spend native SOL from a vault with a spending limit", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const [vault] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const spendingLimit = await createSpendingLimit(ms, PublicKey.default, LAMPORTS_PER_SOL / 10, {day:{}}, [members[0].publicKey]);
+
+      const destination = anchor.web3.Keypair.generate().publicKey;
+      await useSpendingLimit(ms, spendingLimit, vault, destination, members[0], LAMPORTS_PER_SOL / 20);
+      expect(await provider.connection.getBalance(destination)).to.equal(LAMPORTS_PER_SOL / 20);
+      const limitState = await meshProgram.account.spendingLimit.fetch(spendingLimit);
+      expect(limitState.remainingAmount.toNumber()).to.equal(LAMPORTS_PER_SOL / 20);
+
+      // for native SOL the source has to be the vault
+      await expectError("InvalidInstructionAccount", () => useSpendingLimit(ms, spendingLimit, provider.wallet.publicKey, destination, members[0], 1));
+    });
+
+    it("The allowance only restores once the period has passed", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const [vault] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const spendingLimit = await createSpendingLimit(ms, PublicKey.default, LAMPORTS_PER_SOL / 10, {day:{}}, [members[0].publicKey]);
+      const createdState = await meshProgram.account.spendingLimit.fetch(spendingLimit);
+
+      // spending the whole allowance leaves nothing for the rest of the day
+      const destination = anchor.web3.Keypair.generate().publicKey;
+      await useSpendingLimit(ms, spendingLimit, vault, destination, members[0], LAMPORTS_PER_SOL / 10);
+      await expectError("SpendingLimitExceeded", () => useSpendingLimit(ms, spendingLimit, vault, destination, members[0], 1));
+
+      // still within the first period, so the period start hasn't moved
+      const limitState = await meshProgram.account.spendingLimit.fetch(spendingLimit);
+      expect(limitState.remainingAmount.toNumber()).to.equal(0);
+      expect(limitState.lastReset.toNumber()).to.equal(createdState.lastReset.toNumber());
+    });
+
+    it("Spend tokens from a vault token account with a spending limit", async function(){
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const [vault] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      const mint = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 0);
+      const otherMint = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 0);
+      const vaultAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, vault, true);
+      const vaultOtherAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, otherMint, vault, true);
+      const walletAta = await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, provider.wallet.publicKey);
+      await mintTo(provider.connection, payer, mint, vaultAta.address, payer, 1000);
+      await mintTo(provider.connection, payer, otherMint, vaultOtherAta.address, payer, 1000);
+      await mintTo(provider.connection, payer, mint, walletAta.address, payer, 1000);
+      const spendingLimit = await createSpendingLimit(ms, mint, 100, {oneTime:{}}, [members[0].publicKey]);
+
+      const destination = await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, members[0].publicKey);
+      await useSpendingLimit(ms, spendingLimit, vaultAta.address, destination.address, members[0], 60);
+      expect(Number((await getAccount(provider.connection, destination.address)).amount)).to.equal(60);
+
+      // the source must hold the spending limit's mint and be owned by the vault
+      await expectError("InvalidInstructionAccount", () => useSpendingLimit(ms, spendingLimit, vaultOtherAta.address, destination.address, members[0], 10));
+      await expectError("InvalidInstructionAccount", () => useSpendingLimit(ms, spendingLimit, walletAta.address, destination.address, members[0], 10));
+
+      // a one time allowance never restores
+      await expectError("SpendingLimitExceeded", () => useSpendingLimit(ms, spendingLimit, vaultAta.address, destination.address, members[0], 41));
+    });
+
+    it("Only members listed on the spending limit can use it", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const [vault] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const spendingLimit = await createSpendingLimit(ms, PublicKey.default, LAMPORTS_PER_SOL / 10, {day:{}}, [members[0].publicKey]);
+      const destination = anchor.web3.Keypair.generate().publicKey;
+
+      // a multisig member that isn't listed
+      await expectError("MissingPermission", () => useSpendingLimit(ms, spendingLimit, vault, destination, members[1], 1));
+
+      // a key outside the multisig
+      const outsider = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(outsider.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+      await expectError("KeyNotInMultisig", () => useSpendingLimit(ms, spendingLimit, vault, destination, outsider, 1));
+    });
+
+    it("Hand over the external authority with a nomination the new key accepts", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const changeAuthority = (newAuthority: anchor.web3.PublicKey) => meshProgram.methods.changeExternalAuthority(newAuthority)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      const acceptAuthority = (newAuthority: anchor.web3.Keypair) => meshProgram.methods.acceptExternalAuthority()
+        .accounts({
+          multisig: ms,
+          newAuthority: newAuthority.publicKey
+        })
+        .signers([newAuthority])
+        .rpc();
+      const cancelChange = () => meshProgram.methods.cancelExternalAuthorityChange()
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      // nominating doesn't hand anything over yet
+      await changeAuthority(members[0].publicKey);
+      let msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      expect(msState.pendingExternalAuthority.toBase58()).to.equal(members[0].publicKey.toBase58());
+
+      // only the nominated key can accept
+      await expectError("InvalidExternalAuthority", async () => { await acceptAuthority(members[1]); });
+
+      // a cancelled nomination can't be accepted, and there's nothing left to cancel
+      await cancelChange();
+      await expectError("InvalidExternalAuthority", async () => { await acceptAuthority(members[0]); });
+      await expectError("InvalidExternalAuthority", async () => { await cancelChange(); });
+
+      await changeAuthority(members[0].publicKey);
+      await acceptAuthority(members[0]);
+      msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(members[0].publicKey.toBase58());
+      expect(msState.pendingExternalAuthority).to.be.null;
+
+      // the previous authority no longer controls the multisig
+      await expectError("InvalidExternalAuthority", async () => { await changeAuthority(provider.wallet.publicKey); });
+    });
+
+    it("A renounced external authority freezes the configuration", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      await meshProgram.methods.changeExternalAuthority(members[0].publicKey)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      await meshProgram.methods.renounceExternalAuthority()
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      // renouncing also drops any pending nomination
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(PublicKey.default.toBase58());
+      expect(msState.pendingExternalAuthority).to.be.null;
+      await expectError("InvalidExternalAuthority", async () => {
+        await meshProgram.methods.acceptExternalAuthority()
+          .accounts({
+            multisig: ms,
+            newAuthority: members[0].publicKey
+          })
+          .signers([members[0]])
+          .rpc();
+      });
+
+      // config changes fail for good
+      await expectError("ExternalAuthorityRenounced", async () => {
+        await meshProgram.methods.changeThreshold(2, false)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      });
+
+      // members can still propose and approve transactions
+      const tx = await proposeTransaction(ms, 1, []);
+      await approve(ms, tx, members[0]);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+    });
+
+    it("A multisig can't be closed while it has transactions or spending limits", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const closeMultisig = async () => {
+        await meshProgram.methods.closeMultisig()
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey,
+            receiver: provider.wallet.publicKey
+          })
+          .rpc();
+      };
+
+      // an active transaction is still open
+      const tx = await proposeTransaction(ms, 1, []);
+      await expectError("OpenTransactions", closeMultisig);
+
+      // a withdrawn transaction is settled, but its account is still around
+      await meshProgram.methods.withdrawTransaction(false)
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      await expectError("UnclosedTransactions", closeMultisig);
+      await meshProgram.methods.closeTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: members[0].publicKey,
+          member: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+
+      const spendingLimit = await createSpendingLimit(ms, PublicKey.default, LAMPORTS_PER_SOL, {day:{}}, [members[0].publicKey]);
+      await expectError("OpenSpendingLimits", closeMultisig);
+      await meshProgram.methods.removeSpendingLimit()
+        .accounts({
+          multisig: ms,
+          spendingLimit,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      await closeMultisig();
+      expect(await provider.connection.getAccountInfo(ms)).to.be.null;
+    });
+
+    it("A self-governed multisig can't close itself", async function(){
+      const ms = await createMultisig(null, 1);
+      const [internalAuthority] = await getAuthorityPDA(ms, new anchor.BN(0), meshProgram.programId);
+      const closeIx = await meshProgram.methods.closeMultisig()
+        .accounts({
+          multisig: ms,
+          externalAuthority: internalAuthority,
+          receiver: members[0].publicKey
+        })
+        .instruction();
+      const tx = await proposeTransaction(ms, 0, [{ix: closeIx, authorityIndex: null, authorityBump: null}]);
+      await approve(ms, tx, members[0]);
+
+      let failed = false;
+      try {
+        await executeTransaction(tx, members[0] as unknown as anchor.Wallet, provider, meshProgram, members[0].publicKey, [members[0]]);
+      }catch(e){
+        failed = true;
+        expect((e.logs ?? []).join("\n")).to.include("SelfGovernedMultisig");
+      }
+      expect(failed).to.be.true;
+      expect(await provider.connection.getAccountInfo(ms)).to.not.be.null;
+    });
+
+    const waitForSlot = async (slot: number) => {
+      while (await provider.connection.getSlot() <= slot) {
+        await new Promise((resolve) => setTimeout(resolve, 400));
+      }
+    };
+
+    const expireTransaction = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey) => {
+      await meshProgram.methods.expireTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx
+        })
+        .rpc();
+    };
+
+    // executes the next instruction of a transaction, the vault signs through the program
+    const executeInstruction = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, index: number, ix: anchor.web3.TransactionInstruction, member: anchor.web3.Keypair) => {
+      const [instruction] = await getIxPDA(tx, new anchor.BN(index), meshProgram.programId);
+      await meshProgram.methods.executeInstruction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          instruction,
+          member: member.publicKey
+        })
+        .remainingAccounts([
+          {pubkey: ix.programId, isSigner: false, isWritable: false},
+          ...ix.keys.map((k) => ({pubkey: k.pubkey, isSigner: false, isWritable: k.isWritable}))
+        ])
+        .signers([member])
+        .rpc();
+    };
+
+    it("An expired transaction can't be approved and can be marked expired", async function(){
+      this.timeout(60000);
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const expirySlot = await provider.connection.getSlot() + 30;
+      const tx = await proposeTransaction(ms, 1, [], {slot: {0: new anchor.BN(expirySlot)}});
+      await expectError("TransactionNotExpired", () => expireTransaction(ms, tx));
+
+      await waitForSlot(expirySlot);
+      await expectError("TransactionExpired", () => approve(ms, tx, members[0]));
+
+      await expireTransaction(ms, tx);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("expired");
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.openTransactions).to.equal(0);
+    });
+
+    it("A partly executed transaction can finish after its expiry", async function(){
+      this.timeout(60000);
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [vault, vaultBump] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const destination = anchor.web3.Keypair.generate().publicKey;
+      const firstIx = await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10);
+      const secondIx = await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10);
+
+      const expirySlot = await provider.connection.getSlot() + 40;
+      const tx = await proposeTransaction(ms, 1, [
+        {ix: firstIx, authorityIndex: 1, authorityBump: vaultBump},
+        {ix: secondIx, authorityIndex: 1, authorityBump: vaultBump},
+      ], {slot: {0: new anchor.BN(expirySlot)}});
+      await approve(ms, tx, members[0]);
+      await executeInstruction(ms, tx, 1, firstIx, members[0]);
+
+      // past the expiry, the transaction can't be marked expired halfway through
+      await waitForSlot(expirySlot);
+      await expectError("InvalidTransactionState", () => expireTransaction(ms, tx));
+
+      await executeInstruction(ms, tx, 2, secondIx, members[0]);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executed");
+      expect(await provider.connection.getBalance(destination)).to.equal(LAMPORTS_PER_SOL / 5);
+    });
+
+    const addGuardian = async (ms: anchor.web3.PublicKey, guardian: anchor.web3.PublicKey) => {
+      await meshProgram.methods.addGuardian(guardian)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    const vetoTransaction = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, guardian: anchor.web3.Keypair) => {
+      await meshProgram.methods.vetoTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          guardian: guardian.publicKey
+        })
+        .signers([guardian])
+        .rpc();
+    };
+
+    it("Guardians can veto pending transactions but can't be members", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const guardian = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(guardian.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+      await addGuardian(ms, guardian.publicKey);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect((msState.guardians as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal([guardian.publicKey.toBase58()]);
+
+      // a member can't be a guardian and a guardian can't be a member
+      await expectError("InvalidGuardian", () => addGuardian(ms, members[0].publicKey));
+      await expectError("InvalidGuardian", async () => {
+        await meshProgram.methods.addMember(guardian.publicKey, 1, false)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      });
+
+      // only guardians can veto
+      const tx = await proposeTransaction(ms, 1, []);
+      await expectError("InvalidGuardian", () => vetoTransaction(ms, tx, members[0]));
+
+      await vetoTransaction(ms, tx, guardian);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("vetoed");
+      const vetoedMsState = await meshProgram.account.ms.fetch(ms);
+      expect(vetoedMsState.openTransactions).to.equal(0);
+    });
+
+    it("A guardian can't veto a partly executed transaction", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const guardian = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(guardian.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+      await addGuardian(ms, guardian.publicKey);
+
+      const [vault, vaultBump] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const destination = anchor.web3.Keypair.generate().publicKey;
+      const firstIx = await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10);
+      const secondIx = await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10);
+      const tx = await proposeTransaction(ms, 1, [
+        {ix: firstIx, authorityIndex: 1, authorityBump: vaultBump},
+        {ix: secondIx, authorityIndex: 1, authorityBump: vaultBump},
+      ]);
+      await approve(ms, tx, members[0]);
+      await executeInstruction(ms, tx, 1, firstIx, members[0]);
+
+      await expectError("InvalidTransactionState", () => vetoTransaction(ms, tx, guardian));
+      await executeInstruction(ms, tx, 2, secondIx, members[0]);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executed");
+    });
+
+    it("A basis point threshold rounds up and blocks absolute threshold changes", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const setThresholdBps = async (bps: number | null) => {
+        await meshProgram.methods.setThresholdBps(bps, false)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      };
+      const changeThreshold = async (threshold: number) => {
+        await meshProgram.methods.changeThreshold(threshold, false)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      };
+      await expectError("InvalidThreshold", () => setThresholdBps(0));
+      await expectError("InvalidThreshold", () => setThresholdBps(10001));
+
+      // 50% of a total weight of 3 rounds up to 2
+      await setThresholdBps(5000);
+      const tx = await proposeTransaction(ms, 1, []);
+      await approve(ms, tx, members[0]);
+      let txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("active");
+      await approve(ms, tx, members[1]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+
+      // the absolute threshold isn't used, so setting it is refused rather than ignored
+      await expectError("ThresholdBpsEnabled", () => changeThreshold(3));
+      await expectError("ThresholdBpsEnabled", async () => {
+        await meshProgram.methods.reconfigure([], [], [], 3, false)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      });
+
+      await setThresholdBps(null);
+      await changeThreshold(3);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.threshold).to.equal(3);
+      expect(msState.thresholdBps).to.be.null;
+    });
+
+    it("Member removals shrink the multisig account down to the configured slack", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      // key + weight + permissions
+      const memberSize = 32 + 2 + 1;
+      const msAuth = {
+        multisig: ms,
+        externalAuthority: provider.wallet.publicKey
+      };
+      const expectRentExempt = async () => {
+        const info = await provider.connection.getAccountInfo(ms);
+        expect(info.lamports).to.equal(await provider.connection.getMinimumBalanceForRentExemption(info.data.length));
+        return info.data.length;
+      };
+
+      // without slack the account shrinks to exactly the remaining members, refunding the rest
+      const sizeBefore = (await provider.connection.getAccountInfo(ms)).data.length;
+      await meshProgram.methods.setMemberSlack(0).accounts(msAuth).rpc();
+      await meshProgram.methods.removeMember(members[2].publicKey, false).accounts(msAuth).rpc();
+      const compactSize = await expectRentExempt();
+      expect(compactSize).to.be.lessThan(sizeBefore);
+
+      // already compact, nothing to give back
+      await meshProgram.methods.compactMultisig(0).accounts(msAuth).rpc();
+      expect(await expectRentExempt()).to.equal(compactSize);
+
+      // adding grows by a full step, removing shrinks back to the slack
+      const newMember = anchor.web3.Keypair.generate().publicKey;
+      await meshProgram.methods.setMemberSlack(4).accounts(msAuth).rpc();
+      await meshProgram.methods.addMember(newMember, 1, false).accounts(msAuth).rpc();
+      expect(await expectRentExempt()).to.equal(compactSize + 10 * memberSize);
+      await meshProgram.methods.removeMember(newMember, false).accounts(msAuth).rpc();
+      expect(await expectRentExempt()).to.equal(compactSize + 4 * memberSize);
+
+      // compacting takes its own slack
+      await meshProgram.methods.compactMultisig(1).accounts(msAuth).rpc();
+      expect(await expectRentExempt()).to.equal(compactSize + memberSize);
+    });
+
+    const getMetadataPDA = (ms: anchor.web3.PublicKey) => anchor.web3.PublicKey.findProgramAddressSync([
+      Buffer.from("squad"),
+      ms.toBuffer(),
+      Buffer.from("metadata")
+    ], meshProgram.programId);
+
+    // mirrors MultisigMetadata::size_with
+    const metadataSize = (name: string, description: string, uri: string, tags: {key: string, value: string}[]) =>
+      8 + 32 + 4 + name.length + 4 + description.length + 4 + uri.length +
+      4 + tags.reduce((sum, t) => sum + 4 + t.key.length + 4 + t.value.length, 0) + 1;
+
+    it("Create and update multisig metadata, resizing the account to fit", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [metadata] = getMetadataPDA(ms);
+      const accounts = {
+        multisig: ms,
+        metadata,
+        externalAuthority: provider.wallet.publicKey
+      };
+      const expectSize = async (size: number) => {
+        const info = await provider.connection.getAccountInfo(metadata);
+        expect(info.data.length).to.equal(size);
+        expect(info.lamports).to.equal(await provider.connection.getMinimumBalanceForRentExemption(size));
+      };
+
+      const tags = [{key: "team", value: "treasury"}];
+      await meshProgram.methods.createMetadata("mesh", "a test multisig", "https://example.com/mesh.png", tags)
+        .accounts(accounts)
+        .rpc();
+      let metadataState = await meshProgram.account.multisigMetadata.fetch(metadata);
+      expect(metadataState.name).to.equal("mesh");
+      expect(metadataState.tags).to.deep.equal(tags);
+      await expectSize(metadataSize("mesh", "a test multisig", "https://example.com/mesh.png", tags));
+
+      // longer contents grow the account
+      const description = "d".repeat(256);
+      await meshProgram.methods.updateMetadata("mesh", description, "", tags)
+        .accounts(accounts)
+        .rpc();
+      await expectSize(metadataSize("mesh", description, "", tags));
+
+      // shorter contents shrink it and refund the rent
+      await meshProgram.methods.updateMetadata("m", "", "", [])
+        .accounts(accounts)
+        .rpc();
+      await expectSize(metadataSize("m", "", "", []));
+      metadataState = await meshProgram.account.multisigMetadata.fetch(metadata);
+      expect(metadataState.name).to.equal("m");
+      expect(metadataState.tags).to.deep.equal([]);
+    });
+
+    it("Metadata over the length limits is rejected", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [metadata] = getMetadataPDA(ms);
+      const accounts = {
+        multisig: ms,
+        metadata,
+        externalAuthority: provider.wallet.publicKey
+      };
+      const tooManyTags = Array.from({length: 17}, (_, i) => ({key: `k${i}`, value: "v"}));
+
+      await expectError("InvalidMetadata", async () => {
+        await meshProgram.methods.createMetadata("n".repeat(65), "", "", []).accounts(accounts).rpc();
+      });
+      await meshProgram.methods.createMetadata("mesh", "", "", []).accounts(accounts).rpc();
+
+      const invalid = [
+        ["n".repeat(65), "", "", []],
+        ["mesh", "d".repeat(257), "", []],
+        ["mesh", "", "u".repeat(201), []],
+        ["mesh", "", "", tooManyTags],
+        ["mesh", "", "", [{key: "k".repeat(33), value: "v"}]],
+        ["mesh", "", "", [{key: "k", value: "v".repeat(129)}]],
+      ];
+      for (const [name, description, uri, tags] of invalid) {
+        await expectError("InvalidMetadata", async () => {
+          await meshProgram.methods.updateMetadata(name, description, uri, tags).accounts(accounts).rpc();
+        });
+      }
+    });
+
+    const initiateRecovery = async (ms: anchor.web3.PublicKey, recoveryKey: anchor.web3.Keypair, newAuthority: anchor.web3.PublicKey) => {
+      const [recovery] = getRecoveryPDA(ms);
+      await meshProgram.methods.initiateRecovery(newAuthority, [], [], 0)
+        .accounts({
+          multisig: ms,
+          recovery,
+          recoveryKey: recoveryKey.publicKey
+        })
+        .signers([recoveryKey])
+        .rpc();
+    };
+
+    const cancelRecovery = async (ms: anchor.web3.PublicKey, canceller: anchor.web3.Keypair | null) => {
+      const [recovery] = getRecoveryPDA(ms);
+      const recoveryState = await meshProgram.account.recovery.fetch(recovery);
+      await meshProgram.methods.cancelRecovery()
+        .accounts({
+          multisig: ms,
+          recovery,
+          canceller: canceller ? canceller.publicKey : provider.wallet.publicKey,
+          initiator: recoveryState.initiator
+        })
+        .signers(canceller ? [canceller] : [])
+        .rpc();
+    };
+
+    it("An approved recovery can only be finalized after the delay", async function(){
+      this.timeout(60000);
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const newAuthority = anchor.web3.Keypair.generate().publicKey;
+      await setRecovery(ms, [members[0].publicKey], 1, 5);
+      await initiateRecovery(ms, members[0], newAuthority);
+
+      // the threshold is reached right away, but the delay has only just started
+      await expectError("RecoveryDelayNotElapsed", () => finalizeRecovery(ms, members[0].publicKey));
+
+      // the cluster clock follows wall time loosely, so retry until it has moved past the delay
+      await new Promise((resolve) => setTimeout(resolve, 5000));
+      for (let attempt = 0; ; attempt++) {
+        try {
+          await finalizeRecovery(ms, members[0].publicKey);
+          break;
+        }catch(e){
+          expect(e.error.errorCode.code).to.equal("RecoveryDelayNotElapsed");
+          expect(attempt).to.be.lessThan(20);
+          await new Promise((resolve) => setTimeout(resolve, 1000));
+        }
+      }
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(newAuthority.toBase58());
+    });
+
+    it("A member or the external authority can cancel a pending recovery", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [recovery] = getRecoveryPDA(ms);
+      const recoveryKey = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(recoveryKey.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+      await setRecovery(ms, [recoveryKey.publicKey], 1, 3600);
+
+      // a key outside the multisig can't cancel
+      await initiateRecovery(ms, recoveryKey, anchor.web3.Keypair.generate().publicKey);
+      await expectError("InvalidMember", () => cancelRecovery(ms, recoveryKey));
+
+      // a member cancels, refunding the initiator
+      const balanceBefore = await provider.connection.getBalance(recoveryKey.publicKey);
+      await cancelRecovery(ms, members[1]);
+      expect(await provider.connection.getAccountInfo(recovery)).to.be.null;
+      expect(await provider.connection.getBalance(recoveryKey.publicKey)).to.be.greaterThan(balanceBefore);
+
+      // the external authority cancels
+      await initiateRecovery(ms, recoveryKey, anchor.web3.Keypair.generate().publicKey);
+      await cancelRecovery(ms, null);
+      expect(await provider.connection.getAccountInfo(recovery)).to.be.null;
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.externalAuthority.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+    });
+
+    it("A recovery can't be approved once the external authority is renounced", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      await setRecovery(ms, [members[0].publicKey, members[1].publicKey], 2, 0);
+      await initiateRecovery(ms, members[0], anchor.web3.Keypair.generate().publicKey);
+      await meshProgram.methods.renounceExternalAuthority()
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      await expectError("ExternalAuthorityRenounced", () => approveRecovery(ms, members[1]));
+    });
+
+    const closeTransaction = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, member: anchor.web3.Keypair, instructions: anchor.web3.PublicKey[] | null = null) => {
+      const accounts = {
+        multisig: ms,
+        transaction: tx,
+        creator: members[0].publicKey,
+        member: member.publicKey
+      };
+      if (instructions === null) {
+        await meshProgram.methods.closeTransaction()
+          .accounts(accounts)
+          .signers([member])
+          .rpc();
+      } else {
+        await meshProgram.methods.closeTransactionWithInstructions()
+          .accounts(accounts)
+          .remainingAccounts(instructions.map((pubkey) => ({pubkey, isSigner: false, isWritable: true})))
+          .signers([member])
+          .rpc();
+      }
+    };
+
+    it("Close an executed transaction and its instructions, refunding the creator", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [vault, vaultBump] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const destination = anchor.web3.Keypair.generate().publicKey;
+      const firstIx = await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10);
+      const secondIx = await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10);
+      const tx = await proposeTransaction(ms, 1, [
+        {ix: firstIx, authorityIndex: 1, authorityBump: vaultBump},
+        {ix: secondIx, authorityIndex: 1, authorityBump: vaultBump},
+      ]);
+      await approve(ms, tx, members[0]);
+      await executeTransaction(tx, members[0] as unknown as anchor.Wallet, provider, meshProgram, members[0].publicKey, [members[0]]);
+
+      const [ix1] = await getIxPDA(tx, new anchor.BN(1), meshProgram.programId);
+      const [ix2] = await getIxPDA(tx, new anchor.BN(2), meshProgram.programId);
+
+      // the instruction accounts must all be passed, in order
+      await expectError("InvalidNumberOfAccounts", () => closeTransaction(ms, tx, members[1]));
+      await expectError("InvalidNumberOfAccounts", () => closeTransaction(ms, tx, members[1], [ix1]));
+      await expectError("InvalidInstructionAccount", () => closeTransaction(ms, tx, members[1], [ix2, ix1]));
+
+      // another member closes, the rent goes back to the creator
+      let rent = 0;
+      for (const account of [tx, ix1, ix2]) {
+        rent += (await provider.connection.getAccountInfo(account)).lamports;
+      }
+      const creatorBalance = await provider.connection.getBalance(members[0].publicKey);
+      await closeTransaction(ms, tx, members[1], [ix1, ix2]);
+      for (const account of [tx, ix1, ix2]) {
+        expect(await provider.connection.getAccountInfo(account)).to.be.null;
+      }
+      expect(await provider.connection.getBalance(members[0].publicKey)).to.equal(creatorBalance + rent);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.transactionAccounts).to.equal(0);
+    });
+
+    it("Only finished or deprecated transactions can be closed", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+
+      // active and execute ready transactions are still in flight
+      const activeTx = await proposeTransaction(ms, 1, []);
+      await expectError("InvalidTransactionState", () => closeTransaction(ms, activeTx, members[0]));
+      const readyTx = await proposeTransaction(ms, 1, []);
+      await approve(ms, readyTx, members[0]);
+      await approve(ms, readyTx, members[1]);
+      await expectError("InvalidTransactionState", () => closeTransaction(ms, readyTx, members[0]));
+
+      // a config change deprecates the active one, which can then be closed
+      await meshProgram.methods.changeThreshold(1, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      await closeTransaction(ms, activeTx, members[0]);
+      expect(await provider.connection.getAccountInfo(activeTx)).to.be.null;
+      await expectError("InvalidTransactionState", () => closeTransaction(ms, readyTx, members[0]));
+      // the execute ready transaction is still open and still has its account
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.openTransactions).to.equal(1);
+      expect(msState.transactionAccounts).to.equal(1);
+    });
+
+    const withdrawTransaction = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, creator: anchor.web3.Keypair, close: boolean, instructions: anchor.web3.PublicKey[] = []) => {
+      await meshProgram.methods.withdrawTransaction(close)
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: creator.publicKey
+        })
+        .remainingAccounts(instructions.map((pubkey) => ({pubkey, isSigner: false, isWritable: true})))
+        .signers([creator])
+        .rpc();
+    };
+
+    it("The creator can withdraw and close a draft along with its instructions", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const [tx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+      await meshProgram.methods.createTransaction(1, null)
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      const [vault] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      const [ix] = await getIxPDA(tx, new anchor.BN(1), meshProgram.programId);
+      await meshProgram.methods.addInstruction(await createTestTransferTransaction(vault, members[0].publicKey, 1), null, null, {default:{}})
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          instruction: ix,
+          creator: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+
+      const rent = (await provider.connection.getAccountInfo(tx)).lamports + (await provider.connection.getAccountInfo(ix)).lamports;
+      const creatorBalance = await provider.connection.getBalance(members[0].publicKey);
+      await withdrawTransaction(ms, tx, members[0], true, [ix]);
+      expect(await provider.connection.getAccountInfo(tx)).to.be.null;
+      expect(await provider.connection.getAccountInfo(ix)).to.be.null;
+      expect(await provider.connection.getBalance(members[0].publicKey)).to.equal(creatorBalance + rent);
+      const closedMsState = await meshProgram.account.ms.fetch(ms);
+      expect(closedMsState.transactionAccounts).to.equal(0);
+      expect(closedMsState.openTransactions).to.equal(0);
+    });
+
+    it("Only the creator can withdraw an active transaction, and only before any approval", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const tx = await proposeTransaction(ms, 1, []);
+      await expectError("InvalidInstructionAccount", () => withdrawTransaction(ms, tx, members[1], false));
+
+      // withdrawing without closing keeps the account, marked as cancelled
+      await withdrawTransaction(ms, tx, members[0], false);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("cancelled");
+      let msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.openTransactions).to.equal(0);
+      expect(msState.transactionAccounts).to.equal(1);
+
+      // once someone approved, it's up to the vote
+      const approvedTx = await proposeTransaction(ms, 1, []);
+      await approve(ms, approvedTx, members[1]);
+      await expectError("InvalidTransactionState", () => withdrawTransaction(ms, approvedTx, members[0], true));
+      msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.openTransactions).to.equal(1);
+    });
+
+    // creates a multisig of all members with the given weights, returns its address
+    const createWeightedMultisig = async (threshold: number, weights: number[], createKey = anchor.web3.Keypair.generate()) => {
+      const [ms] = await getMsPDA(createKey.publicKey, meshProgram.programId);
+      await meshProgram.methods.create(provider.wallet.publicKey, threshold, members.map((m) => m.publicKey), weights)
+        .accounts({
+          multisig: ms,
+          createKey: createKey.publicKey,
+          creator: provider.wallet.publicKey
+        })
+        .signers([createKey])
+        .rpc();
+      return ms;
+    };
+
+    const reject = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, member: anchor.web3.Keypair) => {
+      await meshProgram.methods.rejectTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          member: member.publicKey
+        })
+        .signers([member])
+        .rpc();
+    };
+
+    const cancel = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, member: anchor.web3.Keypair) => {
+      await meshProgram.methods.cancelTransaction()
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          member: member.publicKey
+        })
+        .signers([member])
+        .rpc();
+    };
+
+    it("Approvals, rejections and cancellations are tallied by weight", async function(){
+      // a founder with weight 3 and two ops keys with weight 1, 3 of 5
+      const ms = await createWeightedMultisig(3, [3, 1, 1]);
+
+      // the two ops keys together don't reach the threshold, the founder does
+      const tx = await proposeTransaction(ms, 1, []);
+      await approve(ms, tx, members[1]);
+      await approve(ms, tx, members[2]);
+      let txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("active");
+      await approve(ms, tx, members[0]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+
+      // cancelling needs the threshold weight as well
+      await cancel(ms, tx, members[1]);
+      await cancel(ms, tx, members[2]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      await cancel(ms, tx, members[0]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("cancelled");
+
+      // rejecting needs enough weight that the threshold can't be reached, 5 - 3 + 1
+      const rejectedTx = await proposeTransaction(ms, 1, []);
+      await reject(ms, rejectedTx, members[1]);
+      await reject(ms, rejectedTx, members[2]);
+      txState = await meshProgram.account.msTransaction.fetch(rejectedTx);
+      expect(txState.status).to.haveOwnProperty("active");
+      await reject(ms, rejectedTx, members[0]);
+      txState = await meshProgram.account.msTransaction.fetch(rejectedTx);
+      expect(txState.status).to.haveOwnProperty("rejected");
+    });
+
+    it("Members need a weight of at least 1 and one weight each", async function(){
+      await expectError("InvalidWeight", () => createWeightedMultisig(1, [1, 0, 1]).then(() => undefined));
+      await expectError("InvalidNumberOfAccounts", () => createWeightedMultisig(1, [1, 1]).then(() => undefined));
+      // the threshold is measured against the total weight
+      await expectError("InvalidThreshold", () => createWeightedMultisig(6, [3, 1, 1]).then(() => undefined));
+
+      const ms = await createWeightedMultisig(1, [3, 1, 1]);
+      await expectError("InvalidWeight", async () => {
+        await meshProgram.methods.addMember(anchor.web3.Keypair.generate().publicKey, 0, false)
+          .accounts({
+            multisig: ms,
+            externalAuthority: provider.wallet.publicKey
+          })
+          .rpc();
+      });
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.weights).to.have.lengthOf(3);
+    });
+
+    // permission flags, as in Ms::PERMISSION_*
+    const INITIATE = 1 << 0;
+    const VOTE = 1 << 1;
+    const EXECUTE = 1 << 2;
+
+    const revokePermissions = async (ms: anchor.web3.PublicKey, member: anchor.web3.PublicKey, permissions: number) => {
+      await meshProgram.methods.revokePermissions(member, permissions, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    const executeEmptyTransaction = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, member: anchor.web3.Keypair) => {
+      await meshProgram.methods.executeTransaction(Buffer.from([]))
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          member: member.publicKey
+        })
+        .signers([member])
+        .rpc();
+    };
+
+    it("Members can be limited to proposing, voting or executing", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [proposer, voter, executor] = members;
+      await revokePermissions(ms, proposer.publicKey, VOTE | EXECUTE);
+      await revokePermissions(ms, voter.publicKey, INITIATE | EXECUTE);
+      await revokePermissions(ms, executor.publicKey, INITIATE | VOTE);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const permissionsOf = (member: anchor.web3.Keypair) =>
+        msState.permissions[(msState.keys as anchor.web3.PublicKey[]).findIndex((k) => k.equals(member.publicKey))];
+      expect(permissionsOf(proposer)).to.equal(INITIATE);
+      expect(permissionsOf(voter)).to.equal(VOTE);
+      expect(permissionsOf(executor)).to.equal(EXECUTE);
+
+      // only the proposer can create transactions
+      for (const member of [voter, executor]) {
+        const [tx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+        await expectError("MissingPermission", async () => {
+          await meshProgram.methods.createTransaction(1, null)
+            .accounts({
+              multisig: ms,
+              transaction: tx,
+              creator: member.publicKey
+            })
+            .signers([member])
+            .rpc();
+        });
+      }
+      const tx = await proposeTransaction(ms, 1, []);
+
+      // only the voter can approve
+      await expectError("MissingPermission", () => approve(ms, tx, proposer));
+      await expectError("MissingPermission", () => approve(ms, tx, executor));
+      await approve(ms, tx, voter);
+      let txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+
+      // only the executor can execute
+      await expectError("MissingPermission", () => executeEmptyTransaction(ms, tx, proposer));
+      await expectError("MissingPermission", () => executeEmptyTransaction(ms, tx, voter));
+      await executeEmptyTransaction(ms, tx, executor);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executed");
+    });
+
+    it("The last member able to vote keeps their vote", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      await revokePermissions(ms, members[0].publicKey, VOTE);
+      await revokePermissions(ms, members[1].publicKey, VOTE);
+      await expectError("InvalidPermissions", () => revokePermissions(ms, members[2].publicKey, VOTE));
+      // flags outside the known permissions are refused
+      await expectError("InvalidPermissions", () => revokePermissions(ms, members[2].publicKey, 1 << 3));
+
+      // granting the vote back restores the member's weight
+      await meshProgram.methods.grantPermissions(members[0].publicKey, VOTE, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      await revokePermissions(ms, members[2].publicKey, VOTE);
+      const tx = await proposeTransaction(ms, 1, []);
+      await approve(ms, tx, members[0]);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+    });
+
+    const setTimeLock = async (ms: anchor.web3.PublicKey, seconds: number) => {
+      await meshProgram.methods.setTimeLock(seconds)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    // waits until the cluster clock reaches the unix timestamp
+    const waitForTimestamp = async (timestamp: number) => {
+      while (await provider.connection.getBlockTime(await provider.connection.getSlot()) < timestamp) {
+        await new Promise((resolve) => setTimeout(resolve, 400));
+      }
+    };
+
+    it("An approved transaction can only be executed once the time lock has passed", async function(){
+      this.timeout(60000);
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      await setTimeLock(ms, 3);
+      const tx = await proposeTransaction(ms, 1, []);
+      await approve(ms, tx, members[0]);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      const readyAt = txState.executeReadyAt.toNumber();
+      expect(readyAt).to.be.greaterThan(0);
+
+      await expectError("TimeLockNotElapsed", () => executeEmptyTransaction(ms, tx, members[0]));
+      await waitForTimestamp(readyAt + 3);
+      await executeEmptyTransaction(ms, tx, members[0]);
+      const executedState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(executedState.status).to.haveOwnProperty("executed");
+    });
+
+    it("The time lock restarts when a re-tally sends a transaction back to voting", async function(){
+      this.timeout(60000);
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      await setTimeLock(ms, 3);
+      const tx = await proposeTransaction(ms, 1, []);
+      await approve(ms, tx, members[0]);
+      await approve(ms, tx, members[1]);
+      let txState = await meshProgram.account.msTransaction.fetch(tx);
+      const firstReadyAt = txState.executeReadyAt.toNumber();
+
+      // an approver leaves, 1 of the 2 needed approvals is left, so executing past the lock demotes it
+      await meshProgram.methods.removeMember(members[1].publicKey, true)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      await waitForTimestamp(firstReadyAt + 3);
+      await executeEmptyTransaction(ms, tx, members[0]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("active");
+
+      // approved again, the lock counts from the new approval
+      await approve(ms, tx, members[2]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      const secondReadyAt = txState.executeReadyAt.toNumber();
+      expect(secondReadyAt).to.be.greaterThan(firstReadyAt);
+      await expectError("TimeLockNotElapsed", () => executeEmptyTransaction(ms, tx, members[0]));
+
+      await waitForTimestamp(secondReadyAt + 3);
+      await executeEmptyTransaction(ms, tx, members[0]);
+      txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executed");
+    });
+
+    const markStale = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey) => {
+      await meshProgram.methods.markStale()
+        .accounts({
+          multisig: ms,
+          transaction: tx
+        })
+        .rpc();
+    };
+
+    it("Anyone can mark transactions deprecated by a config change as stale", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const activeTx = await proposeTransaction(ms, 1, []);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const [draftTx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+      await meshProgram.methods.createTransaction(1, null)
+        .accounts({
+          multisig: ms,
+          transaction: draftTx,
+          creator: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      await expectError("TransactionNotStale", () => markStale(ms, activeTx));
+      await expectError("TransactionNotStale", () => markStale(ms, draftTx));
+
+      await meshProgram.methods.changeThreshold(1, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+
+      // the active transaction was still counted as open
+      await markStale(ms, activeTx);
+      let txState = await meshProgram.account.msTransaction.fetch(activeTx);
+      expect(txState.status).to.haveOwnProperty("stale");
+      let staleMsState = await meshProgram.account.ms.fetch(ms);
+      expect(staleMsState.openTransactions).to.equal(0);
+
+      await markStale(ms, draftTx);
+      txState = await meshProgram.account.msTransaction.fetch(draftTx);
+      expect(txState.status).to.haveOwnProperty("stale");
+      staleMsState = await meshProgram.account.ms.fetch(ms);
+      expect(staleMsState.openTransactions).to.equal(0);
+
+      // stale is final, and transactions created after the change aren't stale
+      await expectError("InvalidTransactionState", () => markStale(ms, activeTx));
+      const newTx = await proposeTransaction(ms, 1, []);
+      await expectError("TransactionNotStale", () => markStale(ms, newTx));
+    });
+
+    const changeRejectCancelThresholds = async (ms: anchor.web3.PublicKey, rejectThreshold: number | null, cancelThreshold: number | null) => {
+      await meshProgram.methods.changeRejectCancelThresholds(rejectThreshold, cancelThreshold, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    it("Rejections and cancellations default to the approval threshold", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.rejectThreshold).to.be.null;
+      expect(msState.cancelThreshold).to.be.null;
+
+      // rejected once 2 of 3 can no longer be reached, 3 - 2 + 1
+      const rejectedTx = await proposeTransaction(ms, 1, []);
+      await reject(ms, rejectedTx, members[0]);
+      let txState = await meshProgram.account.msTransaction.fetch(rejectedTx);
+      expect(txState.status).to.haveOwnProperty("active");
+      await reject(ms, rejectedTx, members[1]);
+      txState = await meshProgram.account.msTransaction.fetch(rejectedTx);
+      expect(txState.status).to.haveOwnProperty("rejected");
+
+      // cancelled with the approval threshold
+      const cancelledTx = await proposeTransaction(ms, 1, []);
+      await approve(ms, cancelledTx, members[0]);
+      await approve(ms, cancelledTx, members[1]);
+      await cancel(ms, cancelledTx, members[0]);
+      txState = await meshProgram.account.msTransaction.fetch(cancelledTx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      await cancel(ms, cancelledTx, members[1]);
+      txState = await meshProgram.account.msTransaction.fetch(cancelledTx);
+      expect(txState.status).to.haveOwnProperty("cancelled");
+    });
+
+    it("Explicit reject and cancel thresholds are used and kept within the members' weight", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      await expectError("InvalidThreshold", () => changeRejectCancelThresholds(ms, 0, null));
+      await expectError("InvalidThreshold", () => changeRejectCancelThresholds(ms, null, 4));
+      await changeRejectCancelThresholds(ms, 1, 3);
+
+      // a single rejection is enough
+      const rejectedTx = await proposeTransaction(ms, 1, []);
+      await reject(ms, rejectedTx, members[2]);
+      let txState = await meshProgram.account.msTransaction.fetch(rejectedTx);
+      expect(txState.status).to.haveOwnProperty("rejected");
+
+      // cancelling takes everyone
+      const cancelledTx = await proposeTransaction(ms, 1, []);
+      await approve(ms, cancelledTx, members[0]);
+      await approve(ms, cancelledTx, members[1]);
+      await cancel(ms, cancelledTx, members[0]);
+      await cancel(ms, cancelledTx, members[1]);
+      txState = await meshProgram.account.msTransaction.fetch(cancelledTx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      await cancel(ms, cancelledTx, members[2]);
+      txState = await meshProgram.account.msTransaction.fetch(cancelledTx);
+      expect(txState.status).to.haveOwnProperty("cancelled");
+
+      // removing a member lowers an explicit threshold that can no longer be reached
+      await changeRejectCancelThresholds(ms, 3, 3);
+      await meshProgram.methods.removeMember(members[2].publicKey, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+      let msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.rejectThreshold).to.equal(2);
+      expect(msState.cancelThreshold).to.equal(2);
+
+      // clearing them restores the defaults
+      await changeRejectCancelThresholds(ms, null, null);
+      msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.rejectThreshold).to.be.null;
+      expect(msState.cancelThreshold).to.be.null;
+    });
+
+    const getDelegationPDA = (ms: anchor.web3.PublicKey, member: anchor.web3.PublicKey) => anchor.web3.PublicKey.findProgramAddressSync([
+      Buffer.from("squad"),
+      ms.toBuffer(),
+      member.toBuffer(),
+      Buffer.from("delegation")
+    ], meshProgram.programId);
+
+    const createDelegation = async (ms: anchor.web3.PublicKey, member: anchor.web3.Keypair, delegate: anchor.web3.PublicKey, expiry: number | null) => {
+      const [delegation] = getDelegationPDA(ms, member.publicKey);
+      await meshProgram.methods.createDelegation(delegate, expiry === null ? null : new anchor.BN(expiry))
+        .accounts({
+          multisig: ms,
+          delegation,
+          member: member.publicKey
+        })
+        .signers([member])
+        .rpc();
+      return delegation;
+    };
+
+    const delegateVote = async (ms: anchor.web3.PublicKey, tx: anchor.web3.PublicKey, member: anchor.web3.PublicKey, delegate: anchor.web3.Keypair, vote: "approve" | "reject") => {
+      const [delegation] = getDelegationPDA(ms, member);
+      const method = vote === "approve" ? meshProgram.methods.delegateApproveTransaction() : meshProgram.methods.delegateRejectTransaction();
+      await method
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          delegation,
+          delegate: delegate.publicKey
+        })
+        .signers([delegate])
+        .rpc();
+    };
+
+    it("A delegate votes on behalf of a member until the delegation is removed", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const delegate = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(delegate.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+      const delegation = await createDelegation(ms, members[0], delegate.publicKey, null);
+
+      // the votes are recorded for the represented member
+      const approvedTx = await proposeTransaction(ms, 1, []);
+      await delegateVote(ms, approvedTx, members[0].publicKey, delegate, "approve");
+      let txState = await meshProgram.account.msTransaction.fetch(approvedTx);
+      expect((txState.approved as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal([members[0].publicKey.toBase58()]);
+
+      const rejectedTx = await proposeTransaction(ms, 1, []);
+      await delegateVote(ms, rejectedTx, members[0].publicKey, delegate, "reject");
+      txState = await meshProgram.account.msTransaction.fetch(rejectedTx);
+      expect((txState.rejected as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal([members[0].publicKey.toBase58()]);
+
+      // only the registered delegate can use the delegation
+      await expectError("InvalidDelegation", () => delegateVote(ms, rejectedTx, members[0].publicKey, members[1], "reject"));
+
+      // once removed, the rent goes back to the member and the delegate can't vote
+      const balanceBefore = await provider.connection.getBalance(members[0].publicKey);
+      await meshProgram.methods.removeDelegation()
+        .accounts({
+          multisig: ms,
+          delegation,
+          member: members[0].publicKey
+        })
+        .signers([members[0]])
+        .rpc();
+      expect(await provider.connection.getAccountInfo(delegation)).to.be.null;
+      expect(await provider.connection.getBalance(members[0].publicKey)).to.be.greaterThan(balanceBefore);
+      await expectError("AccountNotInitialized", () => delegateVote(ms, rejectedTx, members[0].publicKey, delegate, "reject"));
+    });
+
+    it("An expired delegation can't be used", async function(){
+      this.timeout(60000);
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const delegate = anchor.web3.Keypair.generate();
+      const ad = await provider.connection.requestAirdrop(delegate.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(ad);
+      const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+      await createDelegation(ms, members[1], delegate.publicKey, now + 3);
+
+      const tx = await proposeTransaction(ms, 1, []);
+      await waitForTimestamp(now + 3);
+      await expectError("InvalidDelegation", () => delegateVote(ms, tx, members[1].publicKey, delegate, "approve"));
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.approved).to.be.empty;
+    });
+
+    // creates a transaction with all its instructions in one go, returns its address
+    const createTransactionWithInstructions = async (ms: anchor.web3.PublicKey, instructions: {ix: anchor.web3.TransactionInstruction, authorityIndex: number | null, authorityBump: number | null}[], activate: boolean, approve: boolean, instructionAccounts: anchor.web3.PublicKey[] | null = null) => {
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const [tx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+      const ixAccounts = instructionAccounts ?? await Promise.all(instructions.map(async (_, i) => (await getIxPDA(tx, new anchor.BN(i + 1), meshProgram.programId))[0]));
+      await meshProgram.methods.createTransactionWithInstructions(
+        1,
+        null,
+        instructions.map((instruction) => ({
+          instruction: instruction.ix,
+          authorityIndex: instruction.authorityIndex,
+          authorityBump: instruction.authorityBump,
+          authorityType: {default:{}}
+        })),
+        activate,
+        approve
+      )
+        .accounts({
+          multisig: ms,
+          transaction: tx,
+          creator: members[0].publicKey
+        })
+        .remainingAccounts(ixAccounts.map((pubkey) => ({pubkey, isSigner: false, isWritable: true})))
+        .signers([members[0]])
+        .rpc();
+      return tx;
+    };
+
+    it("Create, populate, activate and approve a transaction in one instruction", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [vault, vaultBump] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: LAMPORTS_PER_SOL
+      })));
+      const destination = anchor.web3.Keypair.generate().publicKey;
+      const tx = await createTransactionWithInstructions(ms, [
+        {ix: await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10), authorityIndex: 1, authorityBump: vaultBump},
+        {ix: await createTestTransferTransaction(vault, destination, LAMPORTS_PER_SOL / 10), authorityIndex: 1, authorityBump: vaultBump},
+      ], true, true);
+
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("executeReady");
+      expect(txState.instructionIndex).to.equal(2);
+      expect((txState.approved as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal([members[0].publicKey.toBase58()]);
+      for (const index of [1, 2]) {
+        const [ix] = await getIxPDA(tx, new anchor.BN(index), meshProgram.programId);
+        const ixState = await meshProgram.account.msInstruction.fetch(ix);
+        expect(ixState.instructionIndex).to.equal(index);
+      }
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect(msState.openTransactions).to.equal(1);
+
+      await executeTransaction(tx, members[0] as unknown as anchor.Wallet, provider, meshProgram, members[0].publicKey, [members[0]]);
+      expect(await provider.connection.getBalance(destination)).to.equal(LAMPORTS_PER_SOL / 5);
+    });
+
+    it("Instruction accounts that were prefunded can still be allocated", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [vault, vaultBump] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const [tx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+      const [ix] = await getIxPDA(tx, new anchor.BN(1), meshProgram.programId);
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: ix,
+        lamports: 1000
+      })));
+
+      // left as a draft
+      await createTransactionWithInstructions(ms, [
+        {ix: await createTestTransferTransaction(vault, members[0].publicKey, 1), authorityIndex: 1, authorityBump: vaultBump},
+      ], false, false);
+      const txState = await meshProgram.account.msTransaction.fetch(tx);
+      expect(txState.status).to.haveOwnProperty("draft");
+      const ixInfo = await provider.connection.getAccountInfo(ix);
+      expect(ixInfo.owner.toBase58()).to.equal(meshProgram.programId.toBase58());
+      const ixState = await meshProgram.account.msInstruction.fetch(ix);
+      expect(ixState.programId.toBase58()).to.equal(SystemProgram.programId.toBase58());
+    });
+
+    it("The instruction accounts must match the instructions, and drafts can't be approved", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 1);
+      const [vault, vaultBump] = await getAuthorityPDA(ms, new anchor.BN(1), meshProgram.programId);
+      const instructions = [
+        {ix: await createTestTransferTransaction(vault, members[0].publicKey, 1), authorityIndex: 1, authorityBump: vaultBump},
+        {ix: await createTestTransferTransaction(vault, members[1].publicKey, 1), authorityIndex: 1, authorityBump: vaultBump},
+      ];
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const [tx] = await getTxPDA(ms, new anchor.BN(msState.transactionIndex + 1), meshProgram.programId);
+      const [ix1] = await getIxPDA(tx, new anchor.BN(1), meshProgram.programId);
+      const [ix2] = await getIxPDA(tx, new anchor.BN(2), meshProgram.programId);
+
+      await expectError("InvalidTransactionState", () => createTransactionWithInstructions(ms, instructions, false, true).then(() => undefined));
+      await expectError("InvalidNumberOfAccounts", () => createTransactionWithInstructions(ms, instructions, true, false, [ix1]).then(() => undefined));
+      await expectError("InvalidInstructionAccount", () => createTransactionWithInstructions(ms, instructions, true, false, [ix2, ix1]).then(() => undefined));
+      expect(await provider.connection.getAccountInfo(tx)).to.be.null;
+    });
+
+    const reconfigure = async (ms: anchor.web3.PublicKey, addMembers: anchor.web3.PublicKey[], addWeights: number[], removeMembers: anchor.web3.PublicKey[], threshold: number, retally: boolean) => {
+      await meshProgram.methods.reconfigure(addMembers, addWeights, removeMembers, threshold, retally)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    it("Reconfigure adds and removes members and sets the threshold in one change", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const pendingTx = await proposeTransaction(ms, 1, []);
+      const newMembers = [anchor.web3.Keypair.generate().publicKey, anchor.web3.Keypair.generate().publicKey];
+
+      await reconfigure(ms, newMembers, [2, 1], [members[1].publicKey, members[2].publicKey], 3, false);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const expectedKeys = [members[0].publicKey, ...newMembers].map((k) => k.toBase58()).sort();
+      const keys = (msState.keys as anchor.web3.PublicKey[]).map((k) => k.toBase58());
+      expect(keys).to.deep.equal(expectedKeys);
+      expect(msState.weights[keys.indexOf(newMembers[0].toBase58())]).to.equal(2);
+      expect(msState.threshold).to.equal(3);
+
+      // a single change, deprecating what was in flight at the time
+      expect(msState.msChangeIndex).to.equal(msState.transactionIndex);
+      await expectError("DeprecatedTransaction", () => approve(ms, pendingTx, members[0]));
+
+      // with re-tally, the change index stays put
+      const retalliedTx = await proposeTransaction(ms, 1, []);
+      await reconfigure(ms, [members[1].publicKey], [1], [], 3, true);
+      const retalliedMsState = await meshProgram.account.ms.fetch(ms);
+      expect(retalliedMsState.msChangeIndex).to.equal(msState.msChangeIndex);
+      expect(retalliedMsState.keys).to.have.lengthOf(4);
+      await approve(ms, retalliedTx, members[1]);
+    });
+
+    it("Reconfigure rejects invalid member lists and thresholds as a whole", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const newMember = anchor.web3.Keypair.generate().publicKey;
+      const allMembers = members.map((m) => m.publicKey);
+
+      // existing members can't be added, and only members can be removed
+      await expectError("InvalidMember", () => reconfigure(ms, [members[0].publicKey], [1], [], 2, false));
+      await expectError("InvalidMember", () => reconfigure(ms, [], [], [newMember], 2, false));
+      // and no key can be listed twice
+      await expectError("InvalidMember", () => reconfigure(ms, [newMember, newMember], [1, 1], [], 2, false));
+      await expectError("InvalidMember", () => reconfigure(ms, [], [], [members[0].publicKey, members[0].publicKey], 1, false));
+
+      await expectError("InvalidNumberOfAccounts", () => reconfigure(ms, [newMember], [], [], 2, false));
+      await expectError("InvalidWeight", () => reconfigure(ms, [newMember], [0], [], 2, false));
+
+      // the threshold is checked against the final member set
+      await expectError("InvalidThreshold", () => reconfigure(ms, [newMember], [1], [], 0, false));
+      await expectError("InvalidThreshold", () => reconfigure(ms, [newMember], [1], allMembers.slice(0, 2), 3, false));
+      await expectError("EmptyMembers", () => reconfigure(ms, [], [], allMembers, 1, false));
+
+      const msState = await meshProgram.account.ms.fetch(ms);
+      expect((msState.keys as anchor.web3.PublicKey[]).map((k) => k.toBase58())).to.deep.equal(allMembers.map((k) => k.toBase58()).sort());
+      expect(msState.threshold).to.equal(2);
+      expect(msState.msChangeIndex).to.equal(0);
+    });
+
+    const replaceMember = async (ms: anchor.web3.PublicKey, oldMember: anchor.web3.PublicKey, newMember: anchor.web3.PublicKey) => {
+      await meshProgram.methods.replaceMember(oldMember, newMember, false)
+        .accounts({
+          multisig: ms,
+          externalAuthority: provider.wallet.publicKey
+        })
+        .rpc();
+    };
+
+    it("Replacing a member keeps the seat's weight and permissions and the threshold", async function(){
+      const ms = await createWeightedMultisig(4, [3, 1, 1]);
+      await revokePermissions(ms, members[0].publicKey, EXECUTE);
+      const newMember = anchor.web3.Keypair.generate().publicKey;
+
+      await expectError("InvalidMember", () => replaceMember(ms, newMember, anchor.web3.Keypair.generate().publicKey));
+      await expectError("InvalidMember", () => replaceMember(ms, members[0].publicKey, members[1].publicKey));
+
+      await replaceMember(ms, members[0].publicKey, newMember);
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const keys = (msState.keys as anchor.web3.PublicKey[]).map((k) => k.toBase58());
+      expect(keys).to.deep.equal([newMember, members[1].publicKey, members[2].publicKey].map((k) => k.toBase58()).sort());
+      const ind = keys.indexOf(newMember.toBase58());
+      expect(msState.weights[ind]).to.equal(3);
+      expect(msState.permissions[ind]).to.equal(INITIATE | VOTE);
+      expect(msState.threshold).to.equal(4);
+    });
+
+    const getRotationPDA = (ms: anchor.web3.PublicKey, oldMember: anchor.web3.PublicKey) => anchor.web3.PublicKey.findProgramAddressSync([
+      Buffer.from("squad"),
+      ms.toBuffer(),
+      oldMember.toBuffer(),
+      Buffer.from("rotation")
+    ], meshProgram.programId);
+
+    const requestMemberRotation = async (ms: anchor.web3.PublicKey, oldMember: anchor.web3.Keypair, newMember: anchor.web3.Keypair) => {
+      const [rotation] = getRotationPDA(ms, oldMember.publicKey);
+      await meshProgram.methods.requestMemberRotation()
+        .accounts({
+          multisig: ms,
+          rotation,
+          oldMember: oldMember.publicKey,
+          newMember: newMember.publicKey
+        })
+        .signers([oldMember, newMember])
+        .rpc();
+      return rotation;
+    };
+
+    const approveMemberRotation = async (ms: anchor.web3.PublicKey, oldMember: anchor.web3.PublicKey) => {
+      const [rotation] = getRotationPDA(ms, oldMember);
+      await meshProgram.methods.approveMemberRotation(false)
+        .accounts({
+          multisig: ms,
+          rotation,
+          externalAuthority: provider.wallet.publicKey,
+          oldMember
+        })
+        .rpc();
+    };
+
+    it("A member requests a rotation to a new key that the external authority approves", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const newMember = anchor.web3.Keypair.generate();
+      const rotation = await requestMemberRotation(ms, members[1], newMember);
+      const rotationState = await meshProgram.account.memberRotation.fetch(rotation);
+      expect(rotationState.newMember.toBase58()).to.equal(newMember.publicKey.toBase58());
+
+      await approveMemberRotation(ms, members[1].publicKey);
+      expect(await provider.connection.getAccountInfo(rotation)).to.be.null;
+      const msState = await meshProgram.account.ms.fetch(ms);
+      const keys = (msState.keys as anchor.web3.PublicKey[]).map((k) => k.toBase58());
+      expect(keys).to.include(newMember.publicKey.toBase58());
+      expect(keys).to.not.include(members[1].publicKey.toBase58());
+      expect(msState.threshold).to.equal(2);
+    });
+
+    it("A rotation request can be cancelled by the member", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const rotation = await requestMemberRotation(ms, members[1], anchor.web3.Keypair.generate());
+      await meshProgram.methods.cancelMemberRotation()
+        .accounts({
+          multisig: ms,
+          rotation,
+          oldMember: members[1].publicKey
+        })
+        .signers([members[1]])
+        .rpc();
+      expect(await provider.connection.getAccountInfo(rotation)).to.be.null;
+      await expectError("AccountNotInitialized", () => approveMemberRotation(ms, members[1].publicKey));
+    });
+
+    it("A rotation request needs the new key to co-sign", async function(){
+      const ms = await createMultisig(provider.wallet.publicKey, 2);
+      const newMember = anchor.web3.Keypair.generate();
+      const [rotation] = getRotationPDA(ms, members[1].publicKey);
+      const ix = await meshProgram.methods.requestMemberRotation()
+        .accounts({
+          multisig: ms,
+          rotation,
+          oldMember: members[1].publicKey,
+          newMember: newMember.publicKey
+        })
+        .instruction();
+      // drop the new key's signature
+      ix.keys = ix.keys.map((k) => k.pubkey.equals(newMember.publicKey) ? {...k, isSigner: false} : k);
+
+      let failed = false;
+      try {
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(ix), [members[1]]);
+      }catch(e){
+        failed = true;
+        expect((e.logs ?? []).join("\n")).to.include("AccountNotSigner");
+      }
+      expect(failed).to.be.true;
+      expect(await provider.connection.getAccountInfo(rotation)).to.be.null;
+    });
+  });
+
 });